@@ -0,0 +1,218 @@
+use std::collections::BTreeMap;
+
+use crate::commit_message::CommitMessage;
+use crate::git::RangeCommit;
+
+/// Written at the top of a new changelog file
+const HEADER: &str = "# Changelog
+
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.1.0/),
+and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+";
+
+/// Keep a Changelog sections, in the order they are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Section {
+    Added,
+    Changed,
+    Removed,
+    Fixed,
+    Security,
+}
+
+impl Section {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Section::Added => "Added",
+            Section::Changed => "Changed",
+            Section::Removed => "Removed",
+            Section::Fixed => "Fixed",
+            Section::Security => "Security",
+        }
+    }
+
+    /// Section for a commit; `None` for changes users do not notice, such as `docs` or `ci`
+    fn for_message(message: &CommitMessage) -> Option<Section> {
+        match message.commit_type.to_lowercase().as_str() {
+            "feat" => Some(Section::Added),
+            "fix" if message.scope.as_deref() == Some("security") => Some(Section::Security),
+            "fix" => Some(Section::Fixed),
+            "perf" | "refactor" | "revert" => Some(Section::Changed),
+            _ if message.breaking => Some(Section::Changed),
+            _ => None,
+        }
+    }
+}
+
+/// One line of the changelog
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub scope: Option<String>,
+    pub description: String,
+    pub breaking: bool,
+    /// Abbreviated id of the commit
+    pub commit: String,
+}
+
+/// The changelog section of one release
+#[derive(Debug, Clone)]
+pub struct Release {
+    /// `None` for unreleased changes
+    pub version: Option<String>,
+    pub date: Option<String>,
+    pub summary: Option<String>,
+    pub sections: BTreeMap<Section, Vec<Entry>>,
+    /// Merges, non-conventional commits and types left out of the changelog
+    pub skipped: usize,
+}
+
+impl Release {
+    /// Group conventional `commits` by section; a leading `v` is dropped from the version
+    pub fn new(version: Option<&str>, date: Option<&str>, commits: &[RangeCommit]) -> Self {
+        let mut release = Self {
+            version: version.map(|version| version.strip_prefix('v').unwrap_or(version).to_string()),
+            date: date.map(str::to_string),
+            summary: None,
+            sections: BTreeMap::new(),
+            skipped: 0,
+        };
+        for commit in commits {
+            let message = CommitMessage::parse(&commit.message);
+            match Section::for_message(&message) {
+                Some(section) if !commit.merge => release.sections.entry(section).or_default().push(Entry {
+                    scope: message.scope.clone(),
+                    description: message.description.clone(),
+                    breaking: message.breaking,
+                    commit: commit.id.clone(),
+                }),
+                _ => release.skipped += 1,
+            }
+        }
+        // Breaking changes first, then grouped by scope with unscoped entries last
+        for entries in release.sections.values_mut() {
+            entries.sort_by(|a, b| {
+                b.breaking.cmp(&a.breaking)
+                    .then_with(|| a.scope.is_none().cmp(&b.scope.is_none()))
+                    .then_with(|| a.scope.cmp(&b.scope))
+            });
+        }
+        release
+    }
+
+    pub fn with_summary(mut self, summary: String) -> Self {
+        self.summary = Some(summary.trim().to_string()).filter(|summary| !summary.is_empty());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    pub fn heading(&self) -> String {
+        match (&self.version, &self.date) {
+            (Some(version), Some(date)) => format!("## [{}] - {}", version, date),
+            (Some(version), None) => format!("## [{}]", version),
+            (None, _) => "## [Unreleased]".to_string(),
+        }
+    }
+
+    /// The changelog entries as plain text, as given to the model for the summary
+    pub fn entries_text(&self) -> String {
+        let mut text = String::new();
+        for (section, entries) in &self.sections {
+            text.push_str(&format!("{}:\n", section.title()));
+            for entry in entries {
+                text.push_str(&format!("{}\n", render_entry(entry)));
+            }
+        }
+        text
+    }
+
+    pub fn render(&self) -> String {
+        let mut text = format!("{}\n", self.heading());
+        if let Some(summary) = &self.summary {
+            text.push_str(&format!("\n{}\n", summary));
+        }
+        for (section, entries) in &self.sections {
+            text.push_str(&format!("\n### {}\n\n", section.title()));
+            for entry in entries {
+                text.push_str(&format!("{}\n", render_entry(entry)));
+            }
+        }
+        text
+    }
+}
+
+fn render_entry(entry: &Entry) -> String {
+    format!(
+        "- {}{}{} ({})",
+        if entry.breaking { "**Breaking:** " } else { "" },
+        entry.scope.as_ref().map(|scope| format!("**{}:** ", scope)).unwrap_or_default(),
+        entry.description,
+        entry.commit
+    )
+}
+
+/// `existing` changelog with `release` added above the previous releases. A section with the
+/// same heading, such as an earlier `[Unreleased]`, is replaced.
+pub fn prepend(existing: Option<&str>, release: &Release) -> String {
+    let rendered = release.render();
+    let existing = match existing {
+        Some(existing) if !existing.trim().is_empty() => existing,
+        _ => return format!("{}\n{}", HEADER, rendered),
+    };
+
+    let heading = release.heading();
+    let sections = section_starts(existing);
+    let replaced = sections.iter().position(|&start| existing[start..].lines().next() == Some(heading.as_str()));
+    let (before, after) = match replaced {
+        Some(idx) => {
+            let end = sections.get(idx + 1).copied().unwrap_or_else(|| link_definitions_start(existing));
+            (&existing[..sections[idx]], &existing[end..])
+        }
+        None => {
+            let start = sections.first().copied().unwrap_or_else(|| link_definitions_start(existing));
+            (&existing[..start], &existing[start..])
+        }
+    };
+
+    let mut text = before.trim_end().to_string();
+    text.push_str("\n\n");
+    text.push_str(&rendered);
+    if !after.trim().is_empty() {
+        text.push('\n');
+        text.push_str(after);
+    }
+    text
+}
+
+/// Byte offsets of the `## ` release headings
+fn section_starts(text: &str) -> Vec<usize> {
+    let mut offset = 0;
+    let mut starts = Vec::new();
+    for line in text.split_inclusive('\n') {
+        if line.starts_with("## ") {
+            starts.push(offset);
+        }
+        offset += line.len();
+    }
+    starts
+}
+
+/// Where the trailing `[1.0.0]: https://…` link definitions begin, or the end of the text
+fn link_definitions_start(text: &str) -> usize {
+    let mut start = text.len();
+    let mut offset = text.len();
+    for line in text.split_inclusive('\n').rev() {
+        offset -= line.len();
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && trimmed.contains("]: ") {
+            start = offset;
+        } else if !trimmed.is_empty() {
+            break;
+        }
+    }
+    start
+}
@@ -0,0 +1,357 @@
+use std::error::Error;
+use std::path::Path;
+use git2::{Delta, Diff, DiffFindOptions, Patch};
+use serde::Deserialize;
+
+/// How a file changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeStatus {
+    Added,
+    Deleted,
+    Modified,
+    Renamed,
+    Copied,
+    TypeChange,
+}
+
+impl ChangeStatus {
+    fn from_delta(delta: Delta) -> Option<Self> {
+        match delta {
+            Delta::Added | Delta::Untracked => Some(ChangeStatus::Added),
+            Delta::Deleted => Some(ChangeStatus::Deleted),
+            Delta::Modified => Some(ChangeStatus::Modified),
+            Delta::Renamed => Some(ChangeStatus::Renamed),
+            Delta::Copied => Some(ChangeStatus::Copied),
+            Delta::Typechange => Some(ChangeStatus::TypeChange),
+            Delta::Unmodified | Delta::Ignored | Delta::Unreadable | Delta::Conflicted => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChangeStatus::Added => "added",
+            ChangeStatus::Deleted => "deleted",
+            ChangeStatus::Modified => "modified",
+            ChangeStatus::Renamed => "renamed",
+            ChangeStatus::Copied => "copied",
+            ChangeStatus::TypeChange => "type changed",
+        }
+    }
+}
+
+/// One line of a hunk; `origin` is `+`, `-`, ` ` or a libgit2 end-of-file marker
+#[derive(Debug, Clone)]
+pub struct Line {
+    pub origin: char,
+    pub content: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    /// The `@@ -a,b +c,d @@` line without its trailing newline
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<Line>,
+}
+
+impl Hunk {
+    /// The hunk's lines in unified diff format, without the header
+    pub fn body(&self) -> String {
+        let mut out = String::new();
+        for line in &self.lines {
+            if matches!(line.origin, '+' | '-' | ' ') {
+                out.push(line.origin);
+            }
+            out.push_str(&line.content);
+        }
+        out
+    }
+}
+
+/// Everything that changed about one file
+#[derive(Debug, Clone)]
+pub struct FileDiff {
+    pub path: String,
+    /// Source path of a rename or copy
+    pub old_path: Option<String>,
+    pub status: ChangeStatus,
+    pub binary: bool,
+    /// Old and new file mode when the mode changed, e.g. `(0o100644, 0o100755)`
+    pub mode_change: Option<(u32, u32)>,
+    pub hunks: Vec<Hunk>,
+    /// Diff lines dropped by a truncate rule
+    pub omitted_lines: usize,
+    /// Why the content is left out of the prompt, if it is
+    pub omitted: Option<OmitReason>,
+}
+
+/// Why a changed file is listed without its content
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OmitReason {
+    Binary,
+    /// Matched the skip rule with this pattern
+    Rule(String),
+    /// The diff is larger than the configured limit, in bytes
+    TooLarge(usize),
+}
+
+impl std::fmt::Display for OmitReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OmitReason::Binary => write!(f, "binary file"),
+            OmitReason::Rule(pattern) => write!(f, "matches `{}`", pattern),
+            OmitReason::TooLarge(bytes) => write!(f, "diff of {} KB is too large", bytes.div_ceil(1024)),
+        }
+    }
+}
+
+/// Structured view of a diff, built in a single pass
+#[derive(Debug, Clone, Default)]
+pub struct ChangeSet {
+    pub files: Vec<FileDiff>,
+}
+
+impl ChangeSet {
+    /// Detect renames and copies in `diff`, then collect every file with its hunks
+    pub fn from_diff(diff: &mut Diff) -> Result<Self, Box<dyn Error>> {
+        let mut find_opts = DiffFindOptions::new();
+        find_opts.renames(true).copies(true).for_untracked(true);
+        diff.find_similar(Some(&mut find_opts))?;
+
+        let mut files = Vec::new();
+        for idx in 0..diff.deltas().len() {
+            let Some(patch) = Patch::from_diff(diff, idx)? else { continue };
+            if let Some(file) = FileDiff::from_patch(&patch)? {
+                files.push(file);
+            }
+        }
+        Ok(Self { files })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Leave out or shorten file contents as `filter` asks; files stay listed either way
+    pub fn filtered(mut self, filter: &DiffFilter) -> Self {
+        for file in &mut self.files {
+            if file.omitted.is_some() {
+                continue;
+            }
+            // The first matching rule wins
+            match filter.rules.iter().find(|rule| rule.matches(&file.path)) {
+                Some(DiffRule { action: RuleAction::Skip, pattern, .. }) => file.omit(OmitReason::Rule(pattern.clone())),
+                Some(DiffRule { action: RuleAction::Truncate, max_lines, .. }) => {
+                    file.truncate(max_lines.unwrap_or(DEFAULT_TRUNCATE_LINES));
+                }
+                Some(DiffRule { action: RuleAction::Keep, .. }) | None => {}
+            }
+            let size = file.content_bytes();
+            if size > filter.max_file_bytes {
+                file.omit(OmitReason::TooLarge(size));
+            }
+        }
+        self
+    }
+
+    /// The whole change set as a unified diff
+    pub fn render(&self) -> String {
+        self.files.iter().map(FileDiff::render).collect()
+    }
+}
+
+impl FileDiff {
+    fn from_patch(patch: &Patch) -> Result<Option<Self>, Box<dyn Error>> {
+        let delta = patch.delta();
+        let Some(status) = ChangeStatus::from_delta(delta.status()) else {
+            return Ok(None);
+        };
+        let path_of = |file: git2::DiffFile| file.path().map(|path| path.to_string_lossy().to_string());
+        let path = path_of(delta.new_file()).or_else(|| path_of(delta.old_file())).unwrap_or_default();
+        let old_path = match status {
+            ChangeStatus::Renamed | ChangeStatus::Copied => path_of(delta.old_file()),
+            _ => None,
+        };
+
+        let old_mode = u32::from(delta.old_file().mode());
+        let new_mode = u32::from(delta.new_file().mode());
+        let mode_change = (old_mode != 0 && new_mode != 0 && old_mode != new_mode).then_some((old_mode, new_mode));
+
+        let mut hunks = Vec::with_capacity(patch.num_hunks());
+        for hunk_idx in 0..patch.num_hunks() {
+            let (hunk, line_count) = patch.hunk(hunk_idx)?;
+            let mut lines = Vec::with_capacity(line_count);
+            for line_idx in 0..line_count {
+                let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+                lines.push(Line {
+                    origin: line.origin(),
+                    content: String::from_utf8_lossy(line.content()).to_string(),
+                });
+            }
+            hunks.push(Hunk {
+                header: String::from_utf8_lossy(hunk.header()).trim_end().to_string(),
+                old_start: hunk.old_start(),
+                old_lines: hunk.old_lines(),
+                new_start: hunk.new_start(),
+                new_lines: hunk.new_lines(),
+                lines,
+            });
+        }
+
+        let binary = delta.flags().is_binary();
+        Ok(Some(Self {
+            path,
+            old_path,
+            status,
+            binary,
+            mode_change,
+            hunks,
+            omitted_lines: 0,
+            omitted: binary.then_some(OmitReason::Binary),
+        }))
+    }
+
+    pub fn additions(&self) -> usize {
+        self.hunks.iter().flat_map(|hunk| &hunk.lines).filter(|line| line.origin == '+').count()
+    }
+
+    pub fn deletions(&self) -> usize {
+        self.hunks.iter().flat_map(|hunk| &hunk.lines).filter(|line| line.origin == '-').count()
+    }
+
+    fn omit(&mut self, reason: OmitReason) {
+        self.omitted_lines += self.hunks.iter().map(|hunk| hunk.lines.len()).sum::<usize>();
+        self.hunks.clear();
+        self.omitted = Some(reason);
+    }
+
+    /// Size of the diff lines in bytes
+    fn content_bytes(&self) -> usize {
+        self.hunks.iter().flat_map(|hunk| &hunk.lines).map(|line| line.content.len() + 1).sum()
+    }
+
+    /// Keep only the first `max_lines` diff lines
+    fn truncate(&mut self, max_lines: usize) {
+        let mut kept = 0;
+        for hunk in &mut self.hunks {
+            let keep = hunk.lines.len().min(max_lines - kept);
+            self.omitted_lines += hunk.lines.len() - keep;
+            hunk.lines.truncate(keep);
+            kept += keep;
+        }
+        self.hunks.retain(|hunk| !hunk.lines.is_empty());
+    }
+
+    /// This file's changes in unified diff format
+    pub fn render(&self) -> String {
+        let old_path = self.old_path.as_deref().unwrap_or(&self.path);
+        let mut out = format!("diff --git a/{} b/{}\n", old_path, self.path);
+        match self.status {
+            ChangeStatus::Renamed => out.push_str(&format!("rename from {}\nrename to {}\n", old_path, self.path)),
+            ChangeStatus::Copied => out.push_str(&format!("copy from {}\ncopy to {}\n", old_path, self.path)),
+            ChangeStatus::Added => out.push_str("new file\n"),
+            ChangeStatus::Deleted => out.push_str("deleted file\n"),
+            ChangeStatus::TypeChange => out.push_str("type changed\n"),
+            ChangeStatus::Modified => {}
+        }
+        if let Some((old_mode, new_mode)) = self.mode_change {
+            out.push_str(&format!("old mode {:o}\nnew mode {:o}\n", old_mode, new_mode));
+        }
+        if let Some(reason) = &self.omitted {
+            out.push_str(&format!("changed, content omitted ({})\n", reason));
+            return out;
+        }
+
+        for hunk in &self.hunks {
+            out.push_str(&hunk.header);
+            out.push('\n');
+            out.push_str(&hunk.body());
+        }
+        if self.omitted_lines > 0 {
+            out.push_str(&format!("[... {} more lines omitted]\n", self.omitted_lines));
+        }
+        out
+    }
+}
+
+/// Lines kept by a truncate rule without `max_lines`
+pub const DEFAULT_TRUNCATE_LINES: usize = 20;
+
+/// Diffs larger than this are left out unless configured otherwise
+pub const DEFAULT_MAX_FILE_BYTES: usize = 50_000;
+
+/// Decides which file contents are sent to the model
+#[derive(Debug, Clone)]
+pub struct DiffFilter {
+    /// Checked in order; the first rule matching a path applies
+    pub rules: Vec<DiffRule>,
+    /// Files with larger diffs are listed without their content
+    pub max_file_bytes: usize,
+}
+
+impl Default for DiffFilter {
+    fn default() -> Self {
+        Self { rules: default_rules(), max_file_bytes: DEFAULT_MAX_FILE_BYTES }
+    }
+}
+
+/// What to do with the diff of a file matching a rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleAction {
+    /// Send the whole diff, e.g. to exempt a file from a default rule
+    Keep,
+    /// Leave the file out of the prompt
+    Skip,
+    /// Keep only the start of the file's diff
+    Truncate,
+}
+
+/// Rule for files whose diffs are noise to the model, such as lockfiles and generated code
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DiffRule {
+    /// Glob matched against the file name, or against the whole path when it contains a `/`
+    pub pattern: String,
+    pub action: RuleAction,
+    #[serde(default)]
+    pub max_lines: Option<usize>,
+}
+
+impl DiffRule {
+    pub fn new(pattern: &str, action: RuleAction) -> Self {
+        Self { pattern: pattern.to_string(), action, max_lines: None }
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        let Ok(pattern) = glob::Pattern::new(&self.pattern) else {
+            return false;
+        };
+        if self.pattern.contains('/') {
+            pattern.matches(path)
+        } else {
+            Path::new(path)
+                .file_name()
+                .is_some_and(|name| pattern.matches(&name.to_string_lossy()))
+        }
+    }
+}
+
+/// Skip lockfiles, minified assets, generated sources and vendored directories
+pub fn default_rules() -> Vec<DiffRule> {
+    [
+        // Lockfiles
+        "Cargo.lock", "package-lock.json", "yarn.lock", "pnpm-lock.yaml", "poetry.lock",
+        "Pipfile.lock", "uv.lock", "Gemfile.lock", "composer.lock", "go.sum",
+        // Minified and generated files
+        "*.min.js", "*.min.css", "*.map", "*.pb.go", "*_pb2.py", "*.generated.*",
+        // Vendored dependencies
+        "**/vendor/**", "**/node_modules/**", "**/third_party/**",
+    ]
+    .iter()
+    .map(|pattern| DiffRule::new(pattern, RuleAction::Skip))
+    .collect()
+}
@@ -0,0 +1,184 @@
+use std::path::PathBuf;
+use clap::{Parser, Subcommand};
+
+use crate::git_analysis::Task;
+use crate::modes::{Mode, DEFAULT_MIN_SCORE};
+use crate::review::{ReportFormat, Severity};
+
+/// AI-assisted commit messages and repository analysis
+#[derive(Debug, Parser)]
+#[command(name = "merit-cli-demo", version, about)]
+pub struct Cli {
+    /// Path to the git repository (prompted for in interactive mode when omitted)
+    #[arg(short, long, global = true)]
+    pub repo: Option<String>,
+
+    /// AI provider to use (OpenAI, Claude, DeepSeek, Gemini, Ollama); a comma-separated
+    /// list such as `claude,openai,ollama` falls back to the next one when a provider fails
+    #[arg(short, long, global = true)]
+    pub provider: Option<String>,
+
+    /// Model to request instead of the provider's default
+    #[arg(short, long, global = true)]
+    pub model: Option<String>,
+
+    /// Model for commit messages, overriding --model
+    #[arg(long, global = true)]
+    pub commit_model: Option<String>,
+
+    /// Model for file analysis, overriding --model
+    #[arg(long, global = true)]
+    pub analysis_model: Option<String>,
+
+    /// Model for contributor analysis, overriding --model
+    #[arg(long, global = true)]
+    pub contributor_model: Option<String>,
+
+    /// Model for pull request descriptions, overriding --model
+    #[arg(long, global = true)]
+    pub pr_model: Option<String>,
+
+    /// Model for changelog release summaries, overriding --model
+    #[arg(long, global = true)]
+    pub changelog_model: Option<String>,
+
+    /// Model for code review, overriding --model
+    #[arg(long, global = true)]
+    pub review_model: Option<String>,
+
+    /// Skip all interactive prompts and accept the defaults
+    #[arg(short, long, global = true)]
+    pub yes: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Cli {
+    /// Model requested for one task
+    pub fn task_model(&self, task: Task) -> Option<String> {
+        match task {
+            Task::CommitMessage => self.commit_model.clone(),
+            Task::FileAnalysis => self.analysis_model.clone(),
+            Task::ContributorAnalysis => self.contributor_model.clone(),
+            Task::PullRequest => self.pr_model.clone(),
+            Task::Changelog => self.changelog_model.clone(),
+            Task::Review => self.review_model.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Generate a commit message and commit the changes
+    Commit {
+        /// Describe and commit only the changes already staged in the index
+        #[arg(long)]
+        staged: bool,
+        /// Skip the pre-commit and commit-msg hooks
+        #[arg(long)]
+        no_verify: bool,
+    },
+    /// Install a prepare-commit-msg hook that writes the message for `git commit`
+    InstallHook {
+        /// Replace an existing prepare-commit-msg hook
+        #[arg(long)]
+        force: bool,
+    },
+    /// Fill in the commit message file; run by the prepare-commit-msg hook
+    #[command(hide = true)]
+    PrepareCommitMsg {
+        /// The file holding the commit message
+        file: PathBuf,
+        /// Where the message came from: message, template, merge, squash or commit
+        source: Option<String>,
+        /// The commit being amended or reused
+        commit: Option<String>,
+    },
+    /// Analyze the changes in the working directory, a commit or a range of commits
+    AnalyzeFiles {
+        /// Commit or range to analyze instead, such as `abc123`, `HEAD~5..HEAD` or `main...feature`
+        #[arg(long)]
+        rev: Option<String>,
+        /// Show a merge commit as a combined diff against all parents instead of its first parent
+        #[arg(long)]
+        combined: bool,
+    },
+    /// Describe the changes a branch would bring into its base branch
+    Pr {
+        /// Branch the pull request targets (defaults to the remote's default branch, main or master)
+        #[arg(long)]
+        base: Option<String>,
+        /// Branch or revision being merged
+        #[arg(long)]
+        head: Option<String>,
+        /// Write the description to this file instead of printing it
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Write a Keep a Changelog section for the commits between two tags
+    Changelog {
+        /// Tag of the previous release (defaults to the latest tag before --to)
+        #[arg(long)]
+        from: Option<String>,
+        /// Tag of the release; other revisions are listed as unreleased changes
+        #[arg(long)]
+        to: Option<String>,
+        /// Put a summary written by the model above the entries
+        #[arg(long)]
+        summary: bool,
+        /// Add the section to the top of this changelog file
+        #[arg(long, num_args = 0..=1, default_missing_value = "CHANGELOG.md")]
+        prepend: Option<PathBuf>,
+    },
+    /// Review the changes and report findings with their file, lines and severity
+    Review {
+        /// Review only the changes staged in the index
+        #[arg(long)]
+        staged: bool,
+        /// Report format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        /// Write the report to this file instead of printing it
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Exit with an error when a finding has at least this severity
+        #[arg(long, value_enum)]
+        fail_on: Option<Severity>,
+    },
+    /// Propose better messages for poorly described commits and rewrite them once approved
+    Reword {
+        /// Last commit to keep as it is; the commits after it up to HEAD are checked (defaults to
+        /// the upstream branch). With --yes the proposals are only printed
+        #[arg(long)]
+        from: Option<String>,
+        /// Reword messages scoring below this, out of 100
+        #[arg(long, default_value_t = DEFAULT_MIN_SCORE)]
+        min_score: u8,
+        /// Also rewrite commits that are already on a remote-tracking branch
+        #[arg(long)]
+        force: bool,
+    },
+    /// Analyze contribution patterns
+    Contributors {
+        /// Only analyze contributors whose name or email contains this text
+        #[arg(long)]
+        author: Option<String>,
+    },
+}
+
+impl Command {
+    pub fn into_mode(self) -> Mode {
+        match self {
+            Command::Commit { staged, no_verify } => Mode::CommitMessage { staged_only: staged, no_verify },
+            Command::InstallHook { force } => Mode::InstallHook { force },
+            Command::PrepareCommitMsg { file, source, .. } => Mode::PrepareCommitMsg { file, source },
+            Command::AnalyzeFiles { rev, combined } => Mode::FileAnalysis { rev, combined },
+            Command::Pr { base, head, output } => Mode::PullRequestDescription { base, head, output },
+            Command::Changelog { from, to, summary, prepend } => Mode::Changelog { from, to, summary, prepend },
+            Command::Review { staged, format, output, fail_on } => Mode::Review { staged_only: staged, format, output, fail_on },
+            Command::Reword { from, min_score, force } => Mode::Reword { from, min_score, force },
+            Command::Contributors { author } => Mode::ContributorAnalysis { author },
+        }
+    }
+}
@@ -0,0 +1,378 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use git2::{Config as GitConfig, Oid, Repository};
+use serde::Deserialize;
+
+/// How commits are signed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningMode {
+    /// Never sign
+    Off,
+    /// Sign with GPG, using `user.signingkey` or the committer identity
+    Gpg,
+    /// Sign with the SSH key in `user.signingkey`
+    Ssh,
+    /// Sign when `commit.gpgsign` is set, in the format given by `gpg.format`
+    #[default]
+    Auto,
+}
+
+/// How a commit is created
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CommitOptions {
+    pub signing: SigningMode,
+    /// Skip the `pre-commit` and `commit-msg` hooks, like `git commit --no-verify`
+    pub no_verify: bool,
+}
+
+/// Commit the index of `repo` to the current branch, running hooks and signing as configured
+pub fn create_commit(repo: &Repository, message: &str, options: &CommitOptions) -> Result<Oid, Box<dyn Error>> {
+    let workdir = repo.workdir().ok_or("Cannot commit in a bare repository")?;
+
+    if !options.no_verify {
+        run_hook(repo, workdir, "pre-commit", &[])?;
+    }
+
+    // Hooks see and may rewrite the message through COMMIT_EDITMSG, as with git itself
+    let message_path = repo.path().join("COMMIT_EDITMSG");
+    fs::write(&message_path, ensure_trailing_newline(message))?;
+    if !options.no_verify {
+        run_hook(repo, workdir, "commit-msg", &[message_path.as_os_str()])?;
+    }
+    let message = fs::read_to_string(&message_path)?;
+    if message.trim().is_empty() {
+        return Err("Aborting commit due to empty commit message".into());
+    }
+
+    // Re-read the index, which the pre-commit hook may have changed
+    let mut index = repo.index()?;
+    index.read(true)?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let parent = match repo.head() {
+        Ok(head) => Some(head.peel_to_commit()?),
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => None,
+        Err(e) => return Err(e.into()),
+    };
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    let signature = repo.signature()?;
+
+    let config = repo.config()?;
+    let oid = match resolve_signing(options.signing, &config) {
+        SigningMode::Off | SigningMode::Auto => {
+            repo.commit(Some("HEAD"), &signature, &signature, &message, &tree, &parents)?
+        }
+        mode => {
+            let oid = write_commit(repo, mode, &signature, &signature, &message, &tree, &parents)?;
+            let summary = message.lines().next().unwrap_or_default();
+            let reflog = match parent {
+                Some(_) => format!("commit: {}", summary),
+                None => format!("commit (initial): {}", summary),
+            };
+            update_head(repo, oid, &reflog)?;
+            oid
+        }
+    };
+
+    // Like git, post-commit runs even with --no-verify and cannot undo the commit
+    if let Err(e) = run_hook(repo, workdir, "post-commit", &[]) {
+        eprintln!("⚠️ {}", e);
+    }
+    Ok(oid)
+}
+
+/// Create a commit without moving any reference, signed as `mode` asks
+fn write_commit(
+    repo: &Repository,
+    mode: SigningMode,
+    author: &git2::Signature,
+    committer: &git2::Signature,
+    message: &str,
+    tree: &git2::Tree,
+    parents: &[&git2::Commit],
+) -> Result<Oid, Box<dyn Error>> {
+    if let SigningMode::Off | SigningMode::Auto = mode {
+        return Ok(repo.commit(None, author, committer, message, tree, parents)?);
+    }
+    let config = repo.config()?;
+    let buffer = repo.commit_create_buffer(author, committer, message, tree, parents)?;
+    let buffer = buffer.as_str().ok_or("Commit buffer is not valid UTF-8")?;
+    let commit_signature = match mode {
+        SigningMode::Ssh => sign_ssh(&config, buffer)?,
+        _ => sign_gpg(&config, committer, buffer)?,
+    };
+    Ok(repo.commit_signed(buffer, &commit_signature, None)?)
+}
+
+/// Replace the messages of commits between `from` and HEAD, like a reword rebase. Every commit
+/// after the first reworded one is recreated on its rewritten parents, keeping its tree, author
+/// and merge parents; the committer becomes the current user. The current branch, or a detached
+/// HEAD, then points at the new tip, which is returned.
+pub fn reword_commits(
+    repo: &Repository,
+    from: Option<Oid>,
+    messages: &HashMap<Oid, String>,
+    signing: SigningMode,
+) -> Result<Oid, Box<dyn Error>> {
+    let head = repo.head()?.peel_to_commit()?;
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(head.id())?;
+    if let Some(from) = from {
+        revwalk.hide(from)?;
+    }
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+
+    let mode = resolve_signing(signing, &repo.config()?);
+    let committer = repo.signature()?;
+    let mut rewritten: HashMap<Oid, Oid> = HashMap::new();
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        let parent_ids: Vec<Oid> = commit.parent_ids().map(|id| rewritten.get(&id).copied().unwrap_or(id)).collect();
+        let new_message = messages.get(&commit.id());
+        if new_message.is_none() && parent_ids.iter().copied().eq(commit.parent_ids()) {
+            continue;
+        }
+
+        let parents = parent_ids.iter().map(|&id| repo.find_commit(id)).collect::<Result<Vec<_>, _>>()?;
+        let parents: Vec<&git2::Commit> = parents.iter().collect();
+        let message = match new_message {
+            Some(message) => ensure_trailing_newline(message),
+            None => String::from_utf8_lossy(commit.message_raw_bytes()).into_owned(),
+        };
+        let oid = write_commit(repo, mode, &commit.author(), &committer, &message, &commit.tree()?, &parents)?;
+        rewritten.insert(commit.id(), oid);
+    }
+
+    let tip = rewritten.get(&head.id()).copied().unwrap_or(head.id());
+    if tip != head.id() {
+        update_head(repo, tip, &format!("reword: rewrote {} commit message(s)", messages.len()))?;
+    }
+    Ok(tip)
+}
+
+/// Let the user edit `message` in their editor through `COMMIT_EDITMSG`, returning the text
+/// without comment lines
+pub fn edit_message(repo: &Repository, message: &str) -> Result<String, Box<dyn Error>> {
+    let path = repo.path().join("COMMIT_EDITMSG");
+    fs::write(&path, format!(
+        "{}\n\n# Please edit the commit message for your changes. Lines starting\n# with '#' will be ignored, and an empty message aborts the commit.\n",
+        message
+    ))?;
+
+    let editor = editor(repo);
+    let status = shell_command(&editor)
+        .arg(&path)
+        .current_dir(repo.workdir().unwrap_or(repo.path()))
+        .status()
+        .map_err(|e| format!("Could not start the editor `{}`: {}", editor, e))?;
+    if !status.success() {
+        return Err(format!("The editor `{}` exited with {}", editor, status).into());
+    }
+    Ok(crate::commit_message::strip_comments(&fs::read_to_string(&path)?))
+}
+
+/// The editor git would use: `$GIT_EDITOR`, `core.editor`, `$VISUAL`, `$EDITOR`, then `vi`
+fn editor(repo: &Repository) -> String {
+    let from_env = |name| std::env::var(name).ok().filter(|value: &String| !value.trim().is_empty());
+    from_env("GIT_EDITOR")
+        .or_else(|| repo.config().and_then(|config| config.get_string("core.editor")).ok())
+        .or_else(|| from_env("VISUAL"))
+        .or_else(|| from_env("EDITOR"))
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Editor settings may carry arguments, so they run through the shell like git runs them
+#[cfg(unix)]
+fn shell_command(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(format!("{} \"$@\"", command)).arg(command);
+    shell
+}
+
+#[cfg(not(unix))]
+fn shell_command(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+fn ensure_trailing_newline(message: &str) -> String {
+    if message.ends_with('\n') { message.to_string() } else { format!("{}\n", message) }
+}
+
+/// The concrete signing method; `Auto` only remains when nothing should be signed
+fn resolve_signing(mode: SigningMode, config: &GitConfig) -> SigningMode {
+    match mode {
+        SigningMode::Auto if config.get_bool("commit.gpgsign").unwrap_or(false) => {
+            match config.get_string("gpg.format").as_deref() {
+                Ok("ssh") => SigningMode::Ssh,
+                _ => SigningMode::Gpg,
+            }
+        }
+        mode => mode,
+    }
+}
+
+/// Point the branch HEAD refers to at `oid`, or HEAD itself when it is detached
+fn update_head(repo: &Repository, oid: Oid, reflog: &str) -> Result<(), Box<dyn Error>> {
+    let head = repo.find_reference("HEAD")?;
+    match head.symbolic_target() {
+        Some(branch) => {
+            repo.reference(branch, oid, true, reflog)?;
+        }
+        None => repo.set_head_detached(oid)?,
+    }
+    Ok(())
+}
+
+/// Identifies hooks written by `install_prepare_commit_msg_hook`, which may be overwritten
+const HOOK_MARKER: &str = "# Installed by merit-cli-demo install-hook";
+
+/// Write a `prepare-commit-msg` hook that runs this binary to fill in messages for `git commit`.
+/// A hook written by someone else is only replaced with `force`.
+pub fn install_prepare_commit_msg_hook(repo: &Repository, force: bool) -> Result<PathBuf, Box<dyn Error>> {
+    let dir = hooks_dir(repo);
+    let path = dir.join("prepare-commit-msg");
+    if let Ok(existing) = fs::read_to_string(&path) {
+        if !existing.contains(HOOK_MARKER) && !force {
+            return Err(format!("{} already exists. Use --force to replace it", path.display()).into());
+        }
+    }
+
+    let binary = std::env::current_exe()?;
+    fs::create_dir_all(&dir)?;
+    fs::write(&path, format!(
+        "#!/bin/sh\n{}\n# A failure leaves the message to you instead of aborting the commit\n{} --yes prepare-commit-msg \"$@\" || true\n",
+        HOOK_MARKER,
+        shell_quote(&binary.to_string_lossy()),
+    ))?;
+    set_executable(&path)?;
+    Ok(path)
+}
+
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+fn hooks_dir(repo: &Repository) -> PathBuf {
+    match repo.config().and_then(|config| config.get_path("core.hooksPath")) {
+        Ok(path) if path.is_absolute() => path,
+        Ok(path) => repo.workdir().unwrap_or(repo.path()).join(path),
+        Err(_) => repo.path().join("hooks"),
+    }
+}
+
+/// Run a hook if it exists and is executable; a non-zero exit becomes an error
+fn run_hook(repo: &Repository, workdir: &Path, name: &str, args: &[&std::ffi::OsStr]) -> Result<(), Box<dyn Error>> {
+    let path = hooks_dir(repo).join(name);
+    if !is_executable(&path) {
+        return Ok(());
+    }
+
+    let output = Command::new(&path)
+        .args(args)
+        .current_dir(workdir)
+        .env("GIT_INDEX_FILE", repo.path().join("index"))
+        .env("GIT_EDITOR", ":")
+        .output()
+        .map_err(|e| format!("Could not run the {} hook: {}", name, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "The {} hook failed{}{}",
+            name,
+            if output.stdout.is_empty() && output.stderr.is_empty() { "" } else { ":\n" },
+            String::from_utf8_lossy(&[output.stdout, output.stderr].concat()).trim_end(),
+        ).into());
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Feed `input` to a signing program and return what it prints
+fn run_signer(mut command: Command, input: &str, what: &str) -> Result<String, Box<dyn Error>> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Could not start {} for signing: {}. Set `commit.signing = \"off\"` to commit unsigned", what, e))?;
+    child.stdin.take().ok_or("Signer has no stdin")?.write_all(input.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(format!(
+            "Signing with {} failed: {}\nSet `commit.signing = \"off\"` to commit unsigned",
+            what,
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        ).into());
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+fn sign_gpg(config: &GitConfig, committer: &git2::Signature, buffer: &str) -> Result<String, Box<dyn Error>> {
+    let program = config.get_string("gpg.openpgp.program")
+        .or_else(|_| config.get_string("gpg.program"))
+        .unwrap_or_else(|_| "gpg".to_string());
+    let key = config.get_string("user.signingkey").unwrap_or_else(|_| {
+        format!("{} <{}>", committer.name().unwrap_or_default(), committer.email().unwrap_or_default())
+    });
+
+    let mut command = Command::new(&program);
+    command.args(["--status-fd=2", "-bsau", &key]);
+    run_signer(command, buffer, &program)
+}
+
+fn sign_ssh(config: &GitConfig, buffer: &str) -> Result<String, Box<dyn Error>> {
+    let program = config.get_string("gpg.ssh.program").unwrap_or_else(|_| "ssh-keygen".to_string());
+    let key = config.get_string("user.signingkey")
+        .map_err(|_| "SSH signing needs `user.signingkey` to be set in the git configuration")?;
+
+    // A literal public key is written out so that ssh-keygen can find the matching agent key
+    let (key_path, temporary) = match key.strip_prefix("key::") {
+        Some(literal) => {
+            let path = std::env::temp_dir().join(format!("merit-signing-key-{}.pub", std::process::id()));
+            fs::write(&path, literal)?;
+            (path, true)
+        }
+        None => (expand_home(&key), false),
+    };
+
+    let mut command = Command::new(&program);
+    command.args(["-Y", "sign", "-n", "git", "-f"]).arg(&key_path);
+    let result = run_signer(command, buffer, &program);
+    if temporary {
+        let _ = fs::remove_file(&key_path);
+    }
+    result
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
@@ -0,0 +1,362 @@
+use std::fmt;
+use std::sync::OnceLock;
+use regex::Regex;
+
+/// Width the body is wrapped at
+pub const BODY_WIDTH: usize = 72;
+
+/// A trailer such as `Refs: #123` or `BREAKING CHANGE: drops the v1 API`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Footer {
+    pub token: String,
+    /// `": "`, or `" #"` for references like `Closes #12`
+    pub separator: String,
+    pub value: String,
+}
+
+impl Footer {
+    pub fn new(token: &str, value: &str) -> Self {
+        Self { token: token.to_string(), separator: ": ".to_string(), value: value.to_string() }
+    }
+
+    pub fn is_breaking_change(&self) -> bool {
+        self.token == "BREAKING CHANGE" || self.token == "BREAKING-CHANGE"
+    }
+}
+
+/// Words that make up descriptions like "wip", "fix" or "minor changes"
+const VAGUE_WORDS: &[&str] = &[
+    "wip", "fix", "fixes", "fixed", "fixup", "update", "updates", "updated", "change", "changes",
+    "changed", "misc", "stuff", "things", "tmp", "temp", "test", "tests", "cleanup", "minor",
+    "small", "some", "more", "typo", "done", "work", "progress", "in", "and",
+];
+
+/// Result of `CommitMessage::score`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageScore {
+    /// 100 for a message without problems
+    pub score: u8,
+    pub problems: Vec<String>,
+}
+
+/// A Conventional Commits message: `type(scope)!: description`, a body and footers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitMessage {
+    /// Empty when the header does not follow the Conventional Commits format
+    pub commit_type: String,
+    pub scope: Option<String>,
+    /// Marked with `!` in the header
+    pub breaking: bool,
+    pub description: String,
+    pub body: Option<String>,
+    pub footers: Vec<Footer>,
+}
+
+fn header_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"^(?P<type>[A-Za-z]+)(?:\((?P<scope>[^()]*)\))?(?P<breaking>!)?: ?(?P<description>.*)$")
+            .expect("Header pattern must compile")
+    })
+}
+
+fn footer_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"^(?P<token>BREAKING[ -]CHANGE|[A-Za-z][A-Za-z0-9-]*)(?P<separator>: | #)(?P<value>.*)$")
+            .expect("Footer pattern must compile")
+    })
+}
+
+impl CommitMessage {
+    pub fn new(commit_type: &str, description: &str) -> Self {
+        Self {
+            commit_type: commit_type.to_string(),
+            scope: None,
+            breaking: false,
+            description: description.to_string(),
+            body: None,
+            footers: Vec::new(),
+        }
+    }
+
+    /// Read a message as written by a model or a person. Never fails: a header that is not
+    /// conventional becomes the description, leaving the type empty.
+    pub fn parse(text: &str) -> Self {
+        let text = strip_code_fence(text);
+        let mut lines = text.lines().skip_while(|line| line.trim().is_empty());
+        let header = lines.next().unwrap_or_default().trim();
+        let rest: Vec<&str> = lines.collect();
+
+        let mut message = match header_pattern().captures(header) {
+            Some(caps) => Self {
+                commit_type: caps["type"].to_string(),
+                scope: caps.name("scope").map(|scope| scope.as_str().trim().to_string()).filter(|scope| !scope.is_empty()),
+                breaking: caps.name("breaking").is_some(),
+                description: caps["description"].trim().to_string(),
+                body: None,
+                footers: Vec::new(),
+            },
+            None => Self::new("", header),
+        };
+
+        // Footers form the last paragraph, and every line in it is a footer or a continuation
+        let paragraphs = split_paragraphs(&rest);
+        let mut body_paragraphs = paragraphs.as_slice();
+        if let Some((last, before)) = paragraphs.split_last() {
+            if let Some(footers) = parse_footers(last) {
+                message.footers = footers;
+                body_paragraphs = before;
+            }
+        }
+        let body = body_paragraphs.iter().map(|paragraph| paragraph.join("\n")).collect::<Vec<_>>().join("\n\n");
+        message.body = Some(body).filter(|body| !body.trim().is_empty());
+        message.breaking |= message.footers.iter().any(Footer::is_breaking_change);
+        message
+    }
+
+    /// Whether the header follows `type(scope)!: description`
+    pub fn is_conventional(&self) -> bool {
+        !self.commit_type.is_empty()
+    }
+
+    /// Problems that break the Conventional Commits rules, or the types in `allowed_types`
+    pub fn validate(&self, allowed_types: &[String]) -> Vec<String> {
+        self.rule_violations(allowed_types).into_iter().map(|(_, problem)| problem).collect()
+    }
+
+    /// Problems paired with how many points they cost in `score`
+    fn rule_violations(&self, allowed_types: &[String]) -> Vec<(u8, String)> {
+        let mut problems = Vec::new();
+        if !self.is_conventional() {
+            problems.push((40, "The first line must look like `type(scope): description`".to_string()));
+        } else if !allowed_types.is_empty() && !allowed_types.contains(&self.commit_type) {
+            problems.push((30, format!("Unknown type `{}`, expected one of: {}", self.commit_type, allowed_types.join(", "))));
+        }
+        if self.description.is_empty() {
+            problems.push((50, "The description is empty".to_string()));
+        }
+        let header_length = self.header().chars().count();
+        if header_length > BODY_WIDTH {
+            problems.push((20, format!("The first line is {} characters long, the limit is {}", header_length, BODY_WIDTH)));
+        }
+        problems
+    }
+
+    /// How well the message follows the conventions, from 0 to 100, with the problems found.
+    /// Besides the rules `validate` checks, descriptions such as "wip" or "fix stuff" that do
+    /// not say what changed cost points.
+    pub fn score(&self, allowed_types: &[String]) -> MessageScore {
+        let mut problems = self.rule_violations(allowed_types);
+        let description = self.description.trim_end_matches(['.', '!']).to_lowercase();
+        let words: Vec<&str> = description.split_whitespace().collect();
+        if !words.is_empty() && words.iter().all(|word| VAGUE_WORDS.contains(word)) {
+            problems.push((40, "The description does not say what changed".to_string()));
+        }
+        if self.description.ends_with('.') {
+            problems.push((10, "The description ends with a period".to_string()));
+        }
+
+        let penalty: u32 = problems.iter().map(|(points, _)| *points as u32).sum();
+        MessageScore {
+            score: 100u32.saturating_sub(penalty) as u8,
+            problems: problems.into_iter().map(|(_, problem)| problem).collect(),
+        }
+    }
+
+    /// Mark or unmark the message as breaking; unmarking drops `BREAKING CHANGE` footers too
+    pub fn set_breaking(&mut self, breaking: bool) {
+        self.breaking = breaking;
+        if !breaking {
+            self.footers.retain(|footer| !footer.is_breaking_change());
+        }
+    }
+
+    /// The first line of the message
+    pub fn header(&self) -> String {
+        if !self.is_conventional() {
+            return self.description.clone();
+        }
+        format!(
+            "{}{}{}: {}",
+            self.commit_type,
+            self.scope.as_ref().map(|scope| format!("({})", scope)).unwrap_or_default(),
+            if self.breaking { "!" } else { "" },
+            self.description
+        )
+    }
+}
+
+impl fmt::Display for CommitMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.header())?;
+        if let Some(body) = &self.body {
+            write!(f, "\n\n{}", wrap_body(body, BODY_WIDTH))?;
+        }
+        if !self.footers.is_empty() {
+            writeln!(f)?;
+            for footer in &self.footers {
+                write!(f, "\n{}{}{}", footer.token, footer.separator, footer.value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Directories whose children are separate crates or packages
+const PACKAGE_DIRS: &[&str] = &["crates", "packages", "libs", "apps", "components"];
+
+/// Scope suggested by one changed path: the crate or package it belongs to, else its module
+/// under `src/`, else its top-level directory
+fn path_scope(path: &str) -> Option<String> {
+    let parts: Vec<&str> = path.split('/').collect();
+    let scope = match parts.as_slice() {
+        [dir, name, _, ..] if PACKAGE_DIRS.contains(dir) => *name,
+        ["src", "lib.rs" | "main.rs" | "mod.rs"] => return None,
+        ["src", module, ..] => module.split('.').next().unwrap_or(module),
+        [dir, _, ..] if !dir.starts_with('.') => *dir,
+        _ => return None,
+    };
+    Some(scope.to_string()).filter(|scope| !scope.is_empty())
+}
+
+/// Likely scopes for changes to `paths`, most common first
+pub fn infer_scopes<'a>(paths: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for scope in paths.into_iter().filter_map(path_scope) {
+        match counts.iter_mut().find(|(name, _)| *name == scope) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((scope, 1)),
+        }
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts.into_iter().map(|(scope, _)| scope).collect()
+}
+
+/// Line above which `git commit -v` and `commit.verbose` put the diff
+const SCISSORS: &str = "# ------------------------ >8 ------------------------";
+
+/// Remove `#` comment lines and everything below the scissors line, as git does for messages
+/// written in an editor
+pub fn strip_comments(text: &str) -> String {
+    text.lines()
+        .take_while(|line| *line != SCISSORS)
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Drop a surrounding ``` block that models sometimes add despite being asked not to
+fn strip_code_fence(text: &str) -> &str {
+    let trimmed = text.trim();
+    match trimmed.strip_prefix("```") {
+        Some(rest) => {
+            let rest = rest.split_once('\n').map_or("", |(_, rest)| rest);
+            rest.trim_end().strip_suffix("```").unwrap_or(rest).trim()
+        }
+        None => trimmed,
+    }
+}
+
+fn split_paragraphs<'a>(lines: &[&'a str]) -> Vec<Vec<&'a str>> {
+    let mut paragraphs = Vec::new();
+    let mut current = Vec::new();
+    for line in lines {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push(line.trim_end());
+        }
+    }
+    if !current.is_empty() {
+        paragraphs.push(current);
+    }
+    paragraphs
+}
+
+fn parse_footers(paragraph: &[&str]) -> Option<Vec<Footer>> {
+    let mut footers: Vec<Footer> = Vec::new();
+    for line in paragraph {
+        match footer_pattern().captures(line) {
+            Some(caps) => footers.push(Footer {
+                token: caps["token"].to_string(),
+                separator: caps["separator"].to_string(),
+                value: caps["value"].trim().to_string(),
+            }),
+            // Indented lines continue the previous footer
+            None if line.starts_with(char::is_whitespace) => {
+                let footer = footers.last_mut()?;
+                footer.value.push('\n');
+                footer.value.push_str(line);
+            }
+            None => return None,
+        }
+    }
+    Some(footers)
+}
+
+/// Wrap prose paragraphs at `width`; list items keep their own lines with a hanging indent.
+/// Paragraphs that already fit are left as written.
+pub fn wrap_body(body: &str, width: usize) -> String {
+    let lines: Vec<&str> = body.lines().collect();
+    split_paragraphs(&lines)
+        .iter()
+        .map(|paragraph| {
+            let first = paragraph[0].trim_start();
+            if paragraph.iter().all(|line| line.chars().count() <= width) {
+                paragraph.join("\n")
+            } else if first.starts_with("- ") || first.starts_with("* ") {
+                wrap_list(paragraph, width)
+            } else {
+                wrap_words(&paragraph.join(" "), width, "", "")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Wrap list items, treating lines that are not items as continuations of the previous one
+fn wrap_list(lines: &[&str], width: usize) -> String {
+    let mut items: Vec<String> = Vec::new();
+    for line in lines {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("- ") || trimmed.starts_with("* ") || items.is_empty() {
+            items.push(trimmed.to_string());
+        } else if let Some(item) = items.last_mut() {
+            item.push(' ');
+            item.push_str(trimmed);
+        }
+    }
+    items.iter()
+        .map(|item| match item.split_at_checked(2) {
+            Some((marker, text)) if marker == "- " || marker == "* " => wrap_words(text, width, marker, "  "),
+            _ => wrap_words(item, width, "", ""),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn wrap_words(text: &str, width: usize, first_prefix: &str, prefix: &str) -> String {
+    let mut out = String::new();
+    let mut line = first_prefix.to_string();
+    let mut line_has_words = false;
+    for word in text.split_whitespace() {
+        if line_has_words && line.chars().count() + 1 + word.chars().count() > width {
+            out.push_str(&line);
+            out.push('\n');
+            line = prefix.to_string();
+            line_has_words = false;
+        }
+        if line_has_words {
+            line.push(' ');
+        }
+        line.push_str(word);
+        line_has_words = true;
+    }
+    out.push_str(&line);
+    out
+}
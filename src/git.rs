@@ -1,101 +1,474 @@
-use std::{error::Error, fs, process::Command};
-use git2::{Repository, StatusOptions};
+use std::error::Error;
+use git2::{ApplyLocation, DiffOptions, Repository, StatusOptions};
 
-pub fn get_diff(repo: &Repository) -> Result<String, Box<dyn Error>> {
-    let mut status_opts = StatusOptions::new();
-    status_opts.include_untracked(true);
-    
-    let statuses = repo.statuses(Some(&mut status_opts))?;
-    if statuses.is_empty() {
+use crate::changeset::{ChangeSet, ChangeStatus, DiffFilter, FileDiff};
+use crate::commit::{self, CommitOptions};
+
+/// Which changes a diff describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffScope {
+    /// Staged and unstaged changes, including untracked files
+    WorkingTree,
+    /// Only what is staged in the index
+    Staged,
+}
+
+/// Tree of the HEAD commit, or `None` before the first commit
+fn head_tree(repo: &Repository) -> Result<Option<git2::Tree<'_>>, Box<dyn Error>> {
+    match repo.head() {
+        Ok(head) => Ok(Some(head.peel_to_tree()?)),
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Every change in `scope`, compared against HEAD
+pub fn get_changes(repo: &Repository, scope: DiffScope) -> Result<ChangeSet, Box<dyn Error>> {
+    let head_tree = head_tree(repo)?;
+    let mut diff = match scope {
+        DiffScope::WorkingTree => {
+            let mut diff_opts = DiffOptions::new();
+            diff_opts
+                .include_untracked(true)
+                .recurse_untracked_dirs(true)
+                .show_untracked_content(true);
+            repo.diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(&mut diff_opts))?
+        }
+        DiffScope::Staged => repo.diff_tree_to_index(head_tree.as_ref(), None, None)?,
+    };
+
+    let changes = ChangeSet::from_diff(&mut diff)?;
+    if changes.is_empty() {
         return Err("No changes to commit".into());
     }
+    Ok(changes)
+}
 
-    let mut diff_opts = git2::DiffOptions::new();
-    let mut diff = String::new();
+/// Diff of `scope` as sent to the model, with `filter` applied
+pub fn get_diff(repo: &Repository, scope: DiffScope, filter: &DiffFilter) -> Result<String, Box<dyn Error>> {
+    Ok(get_changes(repo, scope)?.filtered(filter).render())
+}
 
-    for status in statuses.iter() {
-        let path = status.path().unwrap();
-        
-        if status.status().is_wt_new() {
-            // For new files, show their entire content
-            if let Ok(content) = fs::read_to_string(path) {
-                diff.push_str(&format!("New file: {}\n{}\n", path, content));
+/// Each changed file with its own diff, with `filter` applied: the working tree, or the
+/// revision or range `rev`
+pub fn get_file_diffs(repo: &Repository, rev: Option<&str>, filter: &DiffFilter) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let changes = match rev {
+        Some(rev) => get_revision(repo, rev, MergeDiff::FirstParent)?.changes,
+        None => get_changes(repo, DiffScope::WorkingTree)?,
+    };
+    Ok(changes
+        .filtered(filter)
+        .files
+        .iter()
+        .map(|file| (file.path.clone(), file.render()))
+        .collect())
+}
+
+/// A commit between two revisions
+#[derive(Debug, Clone)]
+pub struct RangeCommit {
+    /// Abbreviated commit id
+    pub id: String,
+    pub message: String,
+    /// Has more than one parent
+    pub merge: bool,
+}
+
+/// What a branch adds on top of its base, or what a revision range changed
+#[derive(Debug)]
+pub struct BranchRange {
+    /// Oldest first
+    pub commits: Vec<RangeCommit>,
+    /// Cumulative changes from the merge base to the head
+    pub changes: ChangeSet,
+}
+
+impl BranchRange {
+    /// Commit messages followed by the diff, for the model
+    pub fn render(&self, filter: &DiffFilter) -> String {
+        let mut text = String::from("Commits:\n");
+        for commit in &self.commits {
+            let mut lines = commit.message.trim_end().lines();
+            text.push_str(&format!("- {} {}\n", commit.id, lines.next().unwrap_or_default()));
+            for line in lines {
+                text.push_str(format!("  {}", line).trim_end());
+                text.push('\n');
             }
-        } else {
-            // For modified files, show the diff
-            let old_tree = repo.head()?.peel_to_tree()?;
-            let diff_result = repo.diff_tree_to_workdir_with_index(Some(&old_tree), Some(&mut diff_opts))?;
-            let mut diff_str = String::new();
-            diff_result.print(git2::DiffFormat::Patch, |_, _, line| {
-                diff_str.push_str(&format!("{}", String::from_utf8_lossy(line.content())));
-                true
-            })?;
-            diff.push_str(&diff_str);
         }
+        text.push_str("\nDiff:\n");
+        text.push_str(&self.changes.clone().filtered(filter).render());
+        text
     }
+}
 
-    Ok(diff)
+fn resolve_commit<'r>(repo: &'r Repository, spec: &str) -> Result<git2::Commit<'r>, Box<dyn Error>> {
+    Ok(repo.revparse_single(spec)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|e| format!("Unknown revision `{}`: {}", spec, e.message()))?)
 }
 
-pub fn get_file_diffs(repo: &Repository) -> Result<Vec<(String, String)>, Box<dyn Error>> {
-    let mut status_opts = StatusOptions::new();
-    status_opts.include_untracked(true);
-    
-    let statuses = repo.statuses(Some(&mut status_opts))?;
-    if statuses.is_empty() {
-        return Err("No changes to commit".into());
+/// Commits reachable from `head` but not from `hide`, oldest first
+fn walk_commits(repo: &Repository, head: git2::Oid, hide: Option<git2::Oid>) -> Result<Vec<RangeCommit>, Box<dyn Error>> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(head)?;
+    if let Some(hide) = hide {
+        revwalk.hide(hide)?;
+    }
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+    let mut commits = Vec::new();
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        commits.push(RangeCommit {
+            id: commit.as_object().short_id()?.as_str().unwrap_or_default().to_string(),
+            message: commit.message().unwrap_or_default().to_string(),
+            merge: commit.parent_count() > 1,
+        });
     }
+    Ok(commits)
+}
 
-    let mut diff_opts = git2::DiffOptions::new();
-    let mut file_diffs = Vec::new();
+/// Commits reachable from `head` but not `base`, and the diff from their merge base to `head`,
+/// as a pull request from `head` into `base` would show them
+pub fn get_range(repo: &Repository, base: &str, head: &str) -> Result<BranchRange, Box<dyn Error>> {
+    let (base_commit, head_commit) = (resolve_commit(repo, base)?, resolve_commit(repo, head)?);
+    let merge_base = repo.merge_base(base_commit.id(), head_commit.id())
+        .map_err(|_| format!("`{}` and `{}` have no common history", base, head))?;
 
-    for status in statuses.iter() {
-        let path = status.path().unwrap().to_string();
-        
-        if status.status().is_wt_new() {
-            // For new files, show their entire content
-            if let Ok(content) = fs::read_to_string(&path) {
-                file_diffs.push((path, format!("New file:\n{}", content)));
-            }
-        } else {
-            // For modified files, show only this file's diff
-            let old_tree = repo.head()?.peel_to_tree()?;
-            diff_opts.pathspec(path.clone());
-            let diff_result = repo.diff_tree_to_workdir_with_index(Some(&old_tree), Some(&mut diff_opts))?;
-            let mut diff_str = String::new();
-            diff_result.print(git2::DiffFormat::Patch, |_, _, line| {
-                diff_str.push_str(&format!("{}", String::from_utf8_lossy(line.content())));
-                true
-            })?;
-            if !diff_str.is_empty() {
-                file_diffs.push((path, diff_str));
-            }
-            // Reset pathspec for next iteration
-            diff_opts = git2::DiffOptions::new();
+    let commits = walk_commits(repo, head_commit.id(), Some(merge_base))?;
+    if commits.is_empty() {
+        return Err(format!("`{}` has no commits that are not in `{}`", head, base).into());
+    }
+
+    let base_tree = repo.find_commit(merge_base)?.tree()?;
+    Ok(BranchRange { commits, changes: diff_trees(repo, Some(&base_tree), &head_commit.tree()?)? })
+}
+
+fn diff_trees(repo: &Repository, old: Option<&git2::Tree>, new: &git2::Tree) -> Result<ChangeSet, Box<dyn Error>> {
+    let mut diff = repo.diff_tree_to_tree(old, Some(new), None)?;
+    ChangeSet::from_diff(&mut diff)
+}
+
+/// How a merge commit is compared with its parents
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergeDiff {
+    /// Against the first parent, showing everything the merge brought in
+    #[default]
+    FirstParent,
+    /// Only files that differ from every parent, with a diff against each, like `git show --cc`
+    Combined,
+}
+
+/// A single commit against its parent, or the `a..b` or `a...b` range given by `revspec`
+pub fn get_revision(repo: &Repository, revspec: &str, merge_diff: MergeDiff) -> Result<BranchRange, Box<dyn Error>> {
+    let spec = repo.revparse(revspec).map_err(|e| format!("Unknown revision `{}`: {}", revspec, e.message()))?;
+    if spec.mode().contains(git2::RevparseMode::SINGLE) {
+        let commit = peel_or_head(repo, spec.from())?;
+        let changes = commit_changes(repo, &commit, merge_diff)?;
+        let commits = vec![RangeCommit {
+            id: commit.as_object().short_id()?.as_str().unwrap_or_default().to_string(),
+            message: commit.message().unwrap_or_default().to_string(),
+            merge: commit.parent_count() > 1,
+        }];
+        return Ok(BranchRange { commits, changes });
+    }
+
+    // `a..b` is what b has over a; `a...b` starts from where the two diverged
+    let (from, to) = (peel_or_head(repo, spec.from())?, peel_or_head(repo, spec.to())?);
+    let start = if spec.mode().contains(git2::RevparseMode::MERGE_BASE) {
+        repo.find_commit(repo.merge_base(from.id(), to.id())?)?
+    } else {
+        from
+    };
+    Ok(BranchRange {
+        commits: walk_commits(repo, to.id(), Some(start.id()))?,
+        changes: diff_trees(repo, Some(&start.tree()?), &to.tree()?)?,
+    })
+}
+
+/// The commit `object` points to; an open end of a range such as `HEAD~3..` means HEAD
+fn peel_or_head<'r>(repo: &'r Repository, object: Option<&git2::Object<'r>>) -> Result<git2::Commit<'r>, Box<dyn Error>> {
+    match object {
+        Some(object) => Ok(object.peel_to_commit()?),
+        None => Ok(repo.head()?.peel_to_commit()?),
+    }
+}
+
+fn commit_changes(repo: &Repository, commit: &git2::Commit, merge_diff: MergeDiff) -> Result<ChangeSet, Box<dyn Error>> {
+    let tree = commit.tree()?;
+    if commit.parent_count() < 2 || merge_diff == MergeDiff::FirstParent {
+        let parent_tree = match commit.parent_count() {
+            0 => None,
+            _ => Some(commit.parent(0)?.tree()?),
+        };
+        return diff_trees(repo, parent_tree.as_ref(), &tree);
+    }
+
+    let per_parent = commit.parents()
+        .map(|parent| diff_trees(repo, Some(&parent.tree()?), &tree))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut files = Vec::new();
+    for file in &per_parent[0].files {
+        // A file matching one of the parents was merged without changes of its own
+        let Some(others) = per_parent[1..].iter()
+            .map(|changes| changes.files.iter().find(|other| other.path == file.path))
+            .collect::<Option<Vec<_>>>()
+        else {
+            continue;
+        };
+        let mut combined = file.clone();
+        combined.hunks = std::iter::once(file)
+            .chain(others)
+            .enumerate()
+            .flat_map(|(idx, parent_file)| {
+                parent_file.hunks.iter().map(move |hunk| {
+                    let mut hunk = hunk.clone();
+                    hunk.header = format!("{} against parent {}", hunk.header, idx + 1);
+                    hunk
+                })
+            })
+            .collect();
+        files.push(combined);
+    }
+    Ok(ChangeSet { files })
+}
+
+/// Commits after `from` up to and including `to`, oldest first; the whole history when `from` is `None`
+pub fn get_commits_between(repo: &Repository, from: Option<&str>, to: &str) -> Result<Vec<RangeCommit>, Box<dyn Error>> {
+    let hide = from.map(|from| resolve_commit(repo, from).map(|commit| commit.id())).transpose()?;
+    walk_commits(repo, resolve_commit(repo, to)?.id(), hide)
+}
+
+/// The closest tag on `rev` or one of its ancestors; with `before`, tags on `rev` itself are skipped
+pub fn latest_tag(repo: &Repository, rev: &str, before: bool) -> Result<Option<String>, Box<dyn Error>> {
+    let mut tags: Vec<(git2::Oid, String)> = Vec::new();
+    for name in repo.tag_names(None)?.iter().flatten() {
+        if let Ok(commit) = resolve_commit(repo, &format!("refs/tags/{}", name)) {
+            tags.push((commit.id(), name.to_string()));
+        }
+    }
+
+    let start = resolve_commit(repo, rev)?.id();
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(start)?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+    for oid in revwalk {
+        let oid = oid?;
+        if before && oid == start {
+            continue;
+        }
+        // Of several tags on one commit, the highest sorting one is usually the release
+        if let Some(name) = tags.iter().filter(|(tagged, _)| *tagged == oid).map(|(_, name)| name).max() {
+            return Ok(Some(name.clone()));
+        }
+    }
+    Ok(None)
+}
+
+pub fn is_tag(repo: &Repository, name: &str) -> bool {
+    repo.find_reference(&format!("refs/tags/{}", name)).is_ok()
+}
+
+/// Committer date of `rev` as `YYYY-MM-DD`
+pub fn commit_date(repo: &Repository, rev: &str) -> Result<String, Box<dyn Error>> {
+    let time = resolve_commit(repo, rev)?.time();
+    let date = chrono::DateTime::<chrono::Utc>::from_timestamp(time.seconds() + i64::from(time.offset_minutes()) * 60, 0)
+        .ok_or("Commit time is out of range")?;
+    Ok(date.format("%Y-%m-%d").to_string())
+}
+
+/// The branch pull requests usually target: the remote's default branch, else `main` or `master`
+pub fn default_base_branch(repo: &Repository) -> Option<String> {
+    if let Ok(reference) = repo.find_reference("refs/remotes/origin/HEAD") {
+        if let Some(target) = reference.symbolic_target() {
+            return target.strip_prefix("refs/remotes/").map(str::to_string);
         }
     }
+    ["main", "master", "origin/main", "origin/master"]
+        .into_iter()
+        .find(|name| repo.revparse_single(name).is_ok())
+        .map(str::to_string)
+}
 
-    Ok(file_diffs)
+/// Upstream of the checked-out branch, such as `origin/main`
+pub fn upstream_branch(repo: &Repository) -> Option<String> {
+    let head = repo.head().ok()?;
+    let branch = repo.find_branch(head.shorthand()?, git2::BranchType::Local).ok()?;
+    let upstream = branch.upstream().ok()?;
+    upstream.name().ok()?.map(str::to_string)
 }
 
-pub fn stage_and_commit(repo: &Repository, message: &str) -> Result<(), Box<dyn Error>> {
+/// Commits after `from` up to HEAD, oldest first; the whole history without `from`
+pub fn commits_after<'r>(repo: &'r Repository, from: Option<&str>) -> Result<Vec<git2::Commit<'r>>, Box<dyn Error>> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    if let Some(from) = from {
+        revwalk.hide(resolve_commit(repo, from)?.id())?;
+    }
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+    revwalk.map(|oid| Ok(repo.find_commit(oid?)?)).collect()
+}
+
+/// Those of `commits` reachable from a remote-tracking branch, which others may have fetched
+pub fn pushed_commits(repo: &Repository, commits: &[git2::Oid]) -> Result<Vec<git2::Oid>, Box<dyn Error>> {
+    let tips: Vec<git2::Oid> = repo.references_glob("refs/remotes/*")?
+        .filter_map(|reference| reference.ok()?.resolve().ok()?.target())
+        .collect();
+    Ok(commits.iter()
+        .copied()
+        .filter(|&oid| tips.iter().any(|&tip| tip == oid || repo.graph_descendant_of(tip, oid).unwrap_or(false)))
+        .collect())
+}
+
+/// Stage every change in the working tree, including untracked files and deletions, and commit
+pub fn stage_and_commit(repo: &Repository, message: &str, options: &CommitOptions) -> Result<(), Box<dyn Error>> {
     let mut index = repo.index()?;
     index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)?;
+    index.update_all(["*"].iter(), None)?;
     index.write()?;
     
-    // Use git command directly instead of git2
-    // Uses local signing config instead of recreating logic
-    let output = Command::new("git")
-        .args(["commit", "-S", "-m", message])
-        .output()?;
-        
-    if !output.status.success() {
-        return Err(format!(
-            "Failed to create commit: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ).into());
+    commit_staged(repo, message, options)
+}
+
+/// Commit exactly what is in the index
+pub fn commit_staged(repo: &Repository, message: &str, options: &CommitOptions) -> Result<(), Box<dyn Error>> {
+    commit::create_commit(repo, message, options)?;
+    Ok(())
+}
+
+/// A changed path and where its changes live
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub path: String,
+    /// Has changes in the index
+    pub staged: bool,
+    /// Has changes in the working tree that are not staged, or is untracked
+    pub unstaged: bool,
+}
+
+pub fn get_file_changes(repo: &Repository) -> Result<Vec<FileChange>, Box<dyn Error>> {
+    let mut status_opts = StatusOptions::new();
+    status_opts.include_untracked(true).recurse_untracked_dirs(true);
+
+    let statuses = repo.statuses(Some(&mut status_opts))?;
+    Ok(statuses.iter()
+        .filter_map(|entry| {
+            let status = entry.status();
+            let staged = status.intersects(
+                git2::Status::INDEX_NEW | git2::Status::INDEX_MODIFIED | git2::Status::INDEX_DELETED
+                    | git2::Status::INDEX_RENAMED | git2::Status::INDEX_TYPECHANGE,
+            );
+            let unstaged = status.intersects(
+                git2::Status::WT_NEW | git2::Status::WT_MODIFIED | git2::Status::WT_DELETED
+                    | git2::Status::WT_RENAMED | git2::Status::WT_TYPECHANGE,
+            );
+            Some(FileChange { path: entry.path()?.to_string(), staged, unstaged })
+        })
+        .filter(|change| change.staged || change.unstaged)
+        .collect())
+}
+
+/// Make the index match the working tree for `selected` paths and HEAD for every other changed path.
+/// Selected paths listed in `keep_index` are partly staged files whose index entry stays as it is.
+pub fn stage_files(
+    repo: &Repository,
+    changes: &[FileChange],
+    selected: &[&str],
+    keep_index: &[&str],
+) -> Result<(), Box<dyn Error>> {
+    let head = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let unstage: Vec<&str> = changes.iter()
+        .filter(|change| change.staged && !selected.contains(&change.path.as_str()))
+        .map(|change| change.path.as_str())
+        .collect();
+    if !unstage.is_empty() {
+        repo.reset_default(head.as_ref().map(|commit| commit.as_object()), unstage)?;
+    }
+
+    let workdir = repo.workdir().ok_or("Repository has no working directory")?;
+    let mut index = repo.index()?;
+    index.read(true)?;
+    let to_add = changes.iter().filter(|change| {
+        change.unstaged && selected.contains(&change.path.as_str()) && !keep_index.contains(&change.path.as_str())
+    });
+    for change in to_add {
+        let path = std::path::Path::new(&change.path);
+        if workdir.join(path).exists() {
+            index.add_path(path)?;
+        } else {
+            index.remove_path(path)?;
+        }
+    }
+    index.write()?;
+    Ok(())
+}
+
+fn unstaged_diff(repo: &Repository) -> Result<git2::Diff<'_>, git2::Error> {
+    let mut diff_opts = DiffOptions::new();
+    diff_opts
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true);
+    repo.diff_index_to_workdir(None, Some(&mut diff_opts))
+}
+
+/// Changes in the working tree that are not staged yet
+pub fn get_unstaged_changes(repo: &Repository) -> Result<ChangeSet, Box<dyn Error>> {
+    ChangeSet::from_diff(&mut unstaged_diff(repo)?)
+}
+
+/// Whether `file` can only be staged as a whole: new, deleted, renamed and binary files, and
+/// changes without text hunks such as a mode change
+pub fn is_staged_whole(file: &FileDiff) -> bool {
+    file.status != ChangeStatus::Modified || file.hunks.is_empty()
+}
+
+/// Add unstaged changes to the index. `selected` holds files from `get_unstaged_changes` with
+/// only the chosen hunks kept; files that are staged whole are added as they are.
+pub fn stage_hunks(repo: &Repository, selected: &[FileDiff]) -> Result<(), Box<dyn Error>> {
+    let workdir = repo.workdir().ok_or("Repository has no working directory")?;
+    let mut index = repo.index()?;
+    let mut patch_text = String::new();
+
+    for file in selected {
+        if is_staged_whole(file) {
+            let renamed_from = file.old_path.as_ref().filter(|_| file.status == ChangeStatus::Renamed);
+            for path in renamed_from.into_iter().chain([&file.path]) {
+                let path = std::path::Path::new(path);
+                if workdir.join(path).exists() {
+                    index.add_path(path)?;
+                } else {
+                    index.remove_path(path)?;
+                }
+            }
+            continue;
+        }
+        if file.hunks.is_empty() {
+            continue;
+        }
+
+        patch_text.push_str(&format!("diff --git a/{0} b/{0}\n", file.path));
+        if let Some((old_mode, new_mode)) = file.mode_change {
+            patch_text.push_str(&format!("old mode {:o}\nnew mode {:o}\n", old_mode, new_mode));
+        }
+        patch_text.push_str(&format!("--- a/{0}\n+++ b/{0}\n", file.path));
+        // Hunks left out no longer shift the ones after them, so each new side starts where its
+        // old side does, moved only by the chosen hunks before it
+        let mut offset: i64 = 0;
+        for hunk in &file.hunks {
+            let new_start = i64::from(hunk.old_start) + offset
+                + i64::from(hunk.old_lines == 0)
+                - i64::from(hunk.new_lines == 0);
+            patch_text.push_str(&format!("@@ -{},{} +{},{} @@\n", hunk.old_start, hunk.old_lines, new_start, hunk.new_lines));
+            patch_text.push_str(&hunk.body());
+            offset += i64::from(hunk.new_lines) - i64::from(hunk.old_lines);
+        }
+    }
+    index.write()?;
+
+    if !patch_text.is_empty() {
+        let filtered = git2::Diff::from_buffer(patch_text.as_bytes())?;
+        repo.apply(&filtered, ApplyLocation::Index, None)?;
     }
-    
     Ok(())
 }
 
@@ -201,7 +574,7 @@ pub fn get_contributors(repo: &Repository) -> Result<Vec<ContributorStats>, Box<
             commit_deletions,
             commit.message().unwrap_or("No message").to_string()
         ));
-        stats.largest_commits.sort_by(|a, b| (b.0 + b.1).cmp(&(a.0 + a.1)));
+        stats.largest_commits.sort_by_key(|c| std::cmp::Reverse(c.0 + c.1));
         stats.largest_commits.truncate(5);
 
         if stats.last_commit.is_empty() {
@@ -221,11 +594,11 @@ pub fn get_contributors(repo: &Repository) -> Result<Vec<ContributorStats>, Box<
         }
 
         let mut file_mods: Vec<_> = file_counts.into_iter().collect();
-        file_mods.sort_by(|a, b| b.1.cmp(&a.1));
+        file_mods.sort_by_key(|m| std::cmp::Reverse(m.1));
         stats.most_modified_files = file_mods.into_iter().take(10).collect();
     }
 
-    Ok(processed_contributors.into_iter().map(|(_, stats)| stats).collect())
+    Ok(processed_contributors.into_values().collect())
 }
 
 pub fn get_contributor_commits(repo: &Repository, author_name: &str, author_email: &str) -> Result<Vec<String>, Box<dyn Error>> {
@@ -243,7 +616,7 @@ pub fn get_contributor_commits(repo: &Repository, author_name: &str, author_emai
             let message = commit.message().unwrap_or("No message").to_string();
             let time = commit.time();
             let datetime = chrono::DateTime::<chrono::Utc>::from_timestamp(time.seconds(), 0)
-                .unwrap_or_else(|| chrono::Utc::now())
+                .unwrap_or_else(chrono::Utc::now)
                 .format("%Y-%m-%d %H:%M:%S")
                 .to_string();
             
@@ -1,65 +1,493 @@
 use std::error::Error;
 use std::fmt::Debug;
+use std::sync::Mutex;
 use async_trait::async_trait;
+use futures::future::BoxFuture;
+use futures::{stream, StreamExt, TryStreamExt};
 
-use crate::providers::Provider;
+use crate::commit_message::CommitMessage;
+use crate::providers::{Provider, ProviderError, StreamError, TextStream, MAX_CONCURRENT_REQUESTS};
 
 /// Trait for git-specific model behavior
 #[async_trait]
-pub trait GitAnalyzer: Debug {
+pub trait GitAnalyzer: Debug + Send + Sync {
     fn name(&self) -> &str;
-    async fn generate_commit_message(&self, diff: &str) -> Result<String, Box<dyn Error>>;
+
+    /// Name of the provider that produced the most recent answer
+    fn answered_by(&self) -> String {
+        self.name().to_string()
+    }
+
+    async fn generate_commit_message(&self, diff: &str) -> Result<CommitMessage, Box<dyn Error>>;
     async fn analyze_file_changes(&self, diff: &str) -> Result<String, Box<dyn Error>>;
     async fn analyze_contributor(&self, stats: &str) -> Result<String, Box<dyn Error>>;
+    async fn analyze_file_changes_stream(&self, diff: &str) -> Result<TextStream, Box<dyn Error>>;
+    async fn analyze_contributor_stream(&self, stats: &str) -> Result<TextStream, Box<dyn Error>>;
+    /// Stream a pull request description for a branch's commit messages and diff
+    async fn describe_pull_request_stream(&self, branch: &str) -> Result<TextStream, Box<dyn Error>>;
+    /// A short summary of a release to put above its changelog entries
+    async fn summarize_release(&self, changelog: &str) -> Result<String, Box<dyn Error>>;
+    /// Review findings for one file's annotated diff, as JSON
+    async fn review_file_changes(&self, diff: &str) -> Result<String, Box<dyn Error>>;
+}
+
+/// The kinds of requests a GitAnalyzer makes; each can use its own model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Task {
+    CommitMessage,
+    FileAnalysis,
+    ContributorAnalysis,
+    PullRequest,
+    Changelog,
+    Review,
+}
+
+impl Task {
+    pub const ALL: [Task; 6] = [
+        Task::CommitMessage,
+        Task::FileAnalysis,
+        Task::ContributorAnalysis,
+        Task::PullRequest,
+        Task::Changelog,
+        Task::Review,
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Task::CommitMessage => "commit messages",
+            Task::FileAnalysis => "file analysis",
+            Task::ContributorAnalysis => "contributor analysis",
+            Task::PullRequest => "pull request descriptions",
+            Task::Changelog => "release summaries",
+            Task::Review => "code review",
+        }
+    }
+}
+
+/// One value per task, such as the model or temperature to use for it
+#[derive(Debug, Clone)]
+pub struct PerTask<T> {
+    pub commit_message: T,
+    pub file_analysis: T,
+    pub contributor_analysis: T,
+    pub pull_request: T,
+    pub changelog: T,
+    pub review: T,
+}
+
+/// Model used for each task
+pub type TaskModels = PerTask<String>;
+
+impl<T: Clone> PerTask<T> {
+    /// The same value for every task
+    pub fn uniform(value: impl Into<T>) -> Self {
+        let value = value.into();
+        Self {
+            commit_message: value.clone(),
+            file_analysis: value.clone(),
+            contributor_analysis: value.clone(),
+            pull_request: value.clone(),
+            changelog: value.clone(),
+            review: value,
+        }
+    }
+
+    pub fn get(&self, task: Task) -> &T {
+        match task {
+            Task::CommitMessage => &self.commit_message,
+            Task::FileAnalysis => &self.file_analysis,
+            Task::ContributorAnalysis => &self.contributor_analysis,
+            Task::PullRequest => &self.pull_request,
+            Task::Changelog => &self.changelog,
+            Task::Review => &self.review,
+        }
+    }
+
+    pub fn set(&mut self, task: Task, value: T) {
+        match task {
+            Task::CommitMessage => self.commit_message = value,
+            Task::FileAnalysis => self.file_analysis = value,
+            Task::ContributorAnalysis => self.contributor_analysis = value,
+            Task::PullRequest => self.pull_request = value,
+            Task::Changelog => self.changelog = value,
+            Task::Review => self.review = value,
+        }
+    }
 }
 
 /// Implementation of GitAnalyzer that uses any Provider
 #[derive(Debug)]
 pub struct GitAnalyzerImpl {
     provider: Box<dyn Provider>,
+    models: TaskModels,
+    temperatures: PerTask<f32>,
+    prompts: PerTask<String>,
+    /// Cap on the input tokens of a single request, below the model's context window
+    max_input_tokens: Option<usize>,
 }
 
 impl GitAnalyzerImpl {
-    pub fn new(provider: Box<dyn Provider>) -> Self {
-        Self { provider }
+    pub fn new(provider: Box<dyn Provider>, model: Option<String>) -> Self {
+        let model = model.unwrap_or_else(|| provider.default_model().to_string());
+        Self {
+            provider,
+            models: TaskModels::uniform(model),
+            // Reviews must come back as JSON, which works best with little randomness
+            temperatures: PerTask { review: 0.2, ..PerTask::uniform(0.7) },
+            prompts: PerTask {
+                commit_message: SYSTEM_MESSAGE.to_string(),
+                file_analysis: FILE_ANALYSIS_PROMPT.to_string(),
+                contributor_analysis: CONTRIBUTOR_ANALYSIS_PROMPT.to_string(),
+                pull_request: PULL_REQUEST_PROMPT.to_string(),
+                changelog: RELEASE_SUMMARY_PROMPT.to_string(),
+                review: REVIEW_PROMPT.to_string(),
+            },
+            max_input_tokens: None,
+        }
+    }
+
+    /// Use `models` instead of a single model for every task
+    pub fn with_models(mut self, models: TaskModels) -> Self {
+        self.models = models;
+        self
+    }
+
+    /// Use `model` for one task only
+    pub fn with_task_model(mut self, task: Task, model: String) -> Self {
+        self.models.set(task, model);
+        self
+    }
+
+    pub fn with_temperature(mut self, task: Task, temperature: f32) -> Self {
+        self.temperatures.set(task, temperature);
+        self
+    }
+
+    /// Replace the built-in system prompt of a task
+    pub fn with_prompt(mut self, task: Task, prompt: String) -> Self {
+        self.prompts.set(task, prompt);
+        self
+    }
+
+    pub fn with_max_input_tokens(mut self, tokens: usize) -> Self {
+        self.max_input_tokens = Some(tokens);
+        self
+    }
+
+    /// Tokens left for the input once the system prompt and the answer are accounted for
+    fn input_budget(&self, model: &str, system_prompt: &str) -> usize {
+        let available = self.provider.context_window(model)
+            .saturating_sub(self.provider.estimate_tokens(model, system_prompt) + RESPONSE_TOKENS);
+        self.max_input_tokens
+            .map_or(available, |max| max.min(available))
+            .max(MIN_INPUT_TOKENS)
+    }
+
+    /// Cut `input` at a line boundary so that it fits into `budget` tokens
+    fn truncate_to_budget(&self, model: &str, input: &str, budget: usize) -> String {
+        let tokens = self.provider.estimate_tokens(model, input);
+        if tokens <= budget {
+            return input.to_string();
+        }
+        // Leave room for the note about the truncation
+        let keep_chars = input.chars().count() * budget.saturating_sub(TRUNCATION_NOTE_TOKENS) / tokens;
+        let cut = input.char_indices().nth(keep_chars).map_or(input.len(), |(idx, _)| idx);
+        let cut = input[..cut].rfind('\n').map_or(cut, |idx| idx + 1);
+        format!("{}[... truncated to fit the model's context window]\n", &input[..cut])
+    }
+
+    async fn generate(&self, task: Task, input: &str) -> Result<String, Box<dyn Error>> {
+        let (model, prompt) = (self.models.get(task), self.prompts.get(task));
+        let input = self.truncate_to_budget(model, input, self.input_budget(model, prompt));
+        self.provider
+            .generate_text(model, prompt, &input, *self.temperatures.get(task))
+            .await
+    }
+
+    async fn generate_stream(&self, task: Task, input: &str) -> Result<TextStream, Box<dyn Error>> {
+        let (model, prompt) = (self.models.get(task), self.prompts.get(task));
+        let input = self.truncate_to_budget(model, input, self.input_budget(model, prompt));
+        self.provider
+            .generate_text_stream(model, prompt, &input, *self.temperatures.get(task))
+            .await
+    }
+
+    /// Commit message for a diff too large for one request: summarise the files in batches that
+    /// fit the budget, a few requests at a time, then write the message from the summaries
+    async fn generate_commit_message_from_summaries(&self, diff: &str) -> Result<CommitMessage, Box<dyn Error>> {
+        let model = self.models.get(Task::CommitMessage);
+        let temperature = *self.temperatures.get(Task::CommitMessage);
+        let budget = self.input_budget(model, FILE_SUMMARY_PROMPT);
+        let batches = self.pack_files(model, split_files(diff), budget);
+
+        let requests: Vec<_> = batches.iter()
+            .map(|batch| self.summarize_files(model, batch, budget, temperature))
+            .collect();
+        let summaries: Vec<String> = stream::iter(requests)
+            .buffered(MAX_CONCURRENT_REQUESTS)
+            .try_collect()
+            .await
+            .map_err(|e| e as Box<dyn Error>)?;
+        let summaries = format!(
+            "The diff is too large to show in full. These are summaries of the changes to each file:\n{}",
+            summaries.concat()
+        );
+        Ok(CommitMessage::parse(&self.generate(Task::CommitMessage, &summaries).await?))
+    }
+
+    /// Summary of a batch of file diffs under a heading naming the files. The error is made
+    /// `Send` for the concurrent requests, keeping provider errors intact for the fallback chain.
+    async fn summarize_files(&self, model: &str, batch: &[&str], budget: usize, temperature: f32) -> Result<String, StreamError> {
+        let input = self.truncate_to_budget(model, &batch.concat(), budget);
+        let summary = self.provider.generate_text(model, FILE_SUMMARY_PROMPT, &input, temperature).await
+            .map_err(|e| -> StreamError {
+                match e.downcast::<ProviderError>() {
+                    Ok(e) => e,
+                    Err(e) => e.to_string().into(),
+                }
+            })?;
+        let paths: Vec<&str> = batch.iter().map(|file_diff| file_path(file_diff)).collect();
+        Ok(format!("\n## {}\n{}\n", paths.join(", "), summary.trim()))
+    }
+
+    /// Consecutive file diffs grouped into batches of at most `budget` tokens; a larger file gets
+    /// a batch of its own and is truncated
+    fn pack_files<'d>(&self, model: &str, files: Vec<&'d str>, budget: usize) -> Vec<Vec<&'d str>> {
+        let mut batches: Vec<Vec<&str>> = Vec::new();
+        let mut used = 0;
+        for file_diff in files {
+            let tokens = self.provider.estimate_tokens(model, file_diff);
+            match batches.last_mut() {
+                Some(batch) if used + tokens <= budget => {
+                    batch.push(file_diff);
+                    used += tokens;
+                }
+                _ => {
+                    batches.push(vec![file_diff]);
+                    used = tokens;
+                }
+            }
+        }
+        batches
     }
 }
 
+/// Tokens kept free for the model's answer
+const RESPONSE_TOKENS: usize = 1_024;
+/// Smallest input budget, for models whose window barely fits the system prompt
+const MIN_INPUT_TOKENS: usize = 512;
+const TRUNCATION_NOTE_TOKENS: usize = 16;
+
+/// Split a unified diff into the diffs of the individual files
+fn split_files(diff: &str) -> Vec<&str> {
+    let mut starts: Vec<usize> = diff.match_indices("diff --git ")
+        .map(|(idx, _)| idx)
+        .filter(|&idx| idx == 0 || diff.as_bytes()[idx - 1] == b'\n')
+        .collect();
+    if starts.first() != Some(&0) {
+        starts.insert(0, 0);
+    }
+    starts.iter()
+        .zip(starts.iter().skip(1).chain([&diff.len()]))
+        .map(|(&start, &end)| &diff[start..end])
+        .filter(|file_diff| !file_diff.trim().is_empty())
+        .collect()
+}
+
+/// Path named in the `diff --git a/… b/…` header of a file's diff
+fn file_path(file_diff: &str) -> &str {
+    file_diff.lines()
+        .next()
+        .and_then(|header| header.rsplit_once(" b/"))
+        .map_or("(unknown file)", |(_, path)| path)
+}
+
 #[async_trait]
 impl GitAnalyzer for GitAnalyzerImpl {
     fn name(&self) -> &str {
         self.provider.name()
     }
 
-    async fn generate_commit_message(&self, diff: &str) -> Result<String, Box<dyn Error>> {
-        self.provider.generate_text(SYSTEM_MESSAGE, diff, 0.7).await
+    async fn generate_commit_message(&self, diff: &str) -> Result<CommitMessage, Box<dyn Error>> {
+        let (model, prompt) = (self.models.get(Task::CommitMessage), self.prompts.get(Task::CommitMessage));
+        let fits = self.provider.estimate_tokens(model, diff) <= self.input_budget(model, prompt);
+        if fits || split_files(diff).len() < 2 {
+            return Ok(CommitMessage::parse(&self.generate(Task::CommitMessage, diff).await?));
+        }
+        self.generate_commit_message_from_summaries(diff).await
+    }
+
+    async fn analyze_file_changes(&self, diff: &str) -> Result<String, Box<dyn Error>> {
+        self.generate(Task::FileAnalysis, diff).await
+    }
+
+    async fn analyze_contributor(&self, stats: &str) -> Result<String, Box<dyn Error>> {
+        self.generate(Task::ContributorAnalysis, stats).await
+    }
+
+    async fn analyze_file_changes_stream(&self, diff: &str) -> Result<TextStream, Box<dyn Error>> {
+        self.generate_stream(Task::FileAnalysis, diff).await
+    }
+
+    async fn analyze_contributor_stream(&self, stats: &str) -> Result<TextStream, Box<dyn Error>> {
+        self.generate_stream(Task::ContributorAnalysis, stats).await
+    }
+
+    async fn describe_pull_request_stream(&self, branch: &str) -> Result<TextStream, Box<dyn Error>> {
+        self.generate_stream(Task::PullRequest, branch).await
+    }
+
+    async fn summarize_release(&self, changelog: &str) -> Result<String, Box<dyn Error>> {
+        self.generate(Task::Changelog, changelog).await
+    }
+
+    async fn review_file_changes(&self, diff: &str) -> Result<String, Box<dyn Error>> {
+        self.generate(Task::Review, diff).await
+    }
+}
+
+/// GitAnalyzer that tries several analyzers in order, moving on to the next
+/// one when a request fails with a retryable error. A streamed answer moves on
+/// only until its first chunk arrives; a failure after that ends the answer.
+#[derive(Debug)]
+pub struct FallbackAnalyzer {
+    analyzers: Vec<Box<dyn GitAnalyzer>>,
+    name: String,
+    answered_by: Mutex<Option<String>>,
+}
+
+impl FallbackAnalyzer {
+    pub fn new(analyzers: Vec<Box<dyn GitAnalyzer>>) -> Self {
+        let name = analyzers.iter().map(|a| a.name()).collect::<Vec<_>>().join(" → ");
+        Self { analyzers, name, answered_by: Mutex::new(None) }
+    }
+
+    async fn first_success<'a, T>(
+        &'a self,
+        call: impl Fn(&'a dyn GitAnalyzer) -> BoxFuture<'a, Result<T, Box<dyn Error>>>,
+    ) -> Result<T, Box<dyn Error>> {
+        let mut last_error = None;
+        for analyzer in &self.analyzers {
+            match call(analyzer.as_ref()).await {
+                Ok(value) => {
+                    *self.answered_by.lock().unwrap_or_else(|e| e.into_inner()) = Some(analyzer.answered_by());
+                    return Ok(value);
+                }
+                Err(e) => match e.downcast::<ProviderError>() {
+                    Ok(provider_error) if provider_error.is_retryable() => last_error = Some(provider_error),
+                    Ok(provider_error) => return Err(provider_error),
+                    Err(e) => return Err(e),
+                },
+            }
+        }
+        Err(last_error.unwrap_or(Box::new(ProviderError::NoProvidersAvailable)))
+    }
+
+    /// `first_success` for streams, which can fail with an event such as `overloaded_error`
+    /// after the request itself succeeded
+    async fn first_success_stream<'a>(
+        &'a self,
+        call: impl Fn(&'a dyn GitAnalyzer) -> BoxFuture<'a, Result<TextStream, Box<dyn Error>>>,
+    ) -> Result<TextStream, Box<dyn Error>> {
+        self.first_success(|analyzer| {
+            let request = call(analyzer);
+            Box::pin(async move {
+                let stream = request.await?;
+                first_chunk(stream).await
+            })
+        })
+        .await
+    }
+}
+
+/// Waits for the first chunk so that an error before any text arrives fails the request
+async fn first_chunk(mut stream: TextStream) -> Result<TextStream, Box<dyn Error>> {
+    match stream.next().await {
+        Some(Ok(chunk)) => Ok(Box::pin(stream::once(async { Ok(chunk) }).chain(stream))),
+        Some(Err(e)) => Err(e as Box<dyn Error>),
+        None => Ok(stream),
+    }
+}
+
+#[async_trait]
+impl GitAnalyzer for FallbackAnalyzer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn answered_by(&self) -> String {
+        self.answered_by
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+            .unwrap_or_else(|| self.name.clone())
+    }
+
+    async fn generate_commit_message(&self, diff: &str) -> Result<CommitMessage, Box<dyn Error>> {
+        self.first_success(|a| a.generate_commit_message(diff)).await
     }
 
     async fn analyze_file_changes(&self, diff: &str) -> Result<String, Box<dyn Error>> {
-        self.provider.generate_text(FILE_ANALYSIS_PROMPT, diff, 0.7).await
+        self.first_success(|a| a.analyze_file_changes(diff)).await
     }
 
     async fn analyze_contributor(&self, stats: &str) -> Result<String, Box<dyn Error>> {
-        self.provider.generate_text(CONTRIBUTOR_ANALYSIS_PROMPT, stats, 0.7).await
+        self.first_success(|a| a.analyze_contributor(stats)).await
+    }
+
+    async fn analyze_file_changes_stream(&self, diff: &str) -> Result<TextStream, Box<dyn Error>> {
+        self.first_success_stream(|a| a.analyze_file_changes_stream(diff)).await
+    }
+
+    async fn analyze_contributor_stream(&self, stats: &str) -> Result<TextStream, Box<dyn Error>> {
+        self.first_success_stream(|a| a.analyze_contributor_stream(stats)).await
+    }
+
+    async fn describe_pull_request_stream(&self, branch: &str) -> Result<TextStream, Box<dyn Error>> {
+        self.first_success_stream(|a| a.describe_pull_request_stream(branch)).await
+    }
+
+    async fn summarize_release(&self, changelog: &str) -> Result<String, Box<dyn Error>> {
+        self.first_success(|a| a.summarize_release(changelog)).await
+    }
+
+    async fn review_file_changes(&self, diff: &str) -> Result<String, Box<dyn Error>> {
+        self.first_success(|a| a.review_file_changes(diff)).await
     }
 }
 
-pub fn wrap_provider(provider: Box<dyn Provider>) -> Box<dyn GitAnalyzer> {
-    Box::new(GitAnalyzerImpl::new(provider))
+pub fn wrap_provider(provider: Box<dyn Provider>, model: Option<String>) -> Box<dyn GitAnalyzer> {
+    Box::new(GitAnalyzerImpl::new(provider, model))
 }
 
 const SYSTEM_MESSAGE: &str = r#"You are an expert software developer tasked with writing clear, concise, and informative git commit messages following the Conventional Commits specification. Given a git diff, you will:
 
 1. Analyze the changes to understand what was modified
-2. Create a commit message following these rules:
-   - Use the conventional commit format: <type>: <description>
-   - Common types are: feat (new feature), fix (bug fix), docs (documentation), style (formatting), refactor, test, chore
+2. Create a commit message with this structure:
+
+   <type>[optional scope][!]: <description>
+
+   [optional body]
+
+   [optional footers]
+
+3. Follow these rules:
+   - Types are: feat (new feature), fix (bug fix), docs (documentation), style (formatting), refactor, perf (performance), test, build (build system or dependencies), ci, chore, revert
+   - Add a scope naming the crate, package or module most of the changes are in, e.g. `fix(parser): ...`; leave it out when the changes are spread across the project
    - The description should use imperative mood ("Add feature" not "Added feature")
-   - The entire message should be 50 chars or less
-   - Focus on the "why" and "what" rather than the "how"
+   - The first line should be 50 chars or less
+   - Add a body for anything beyond a trivial change. Explain why the change was made and what it affects rather than how; separate it from the first line with a blank line
+   - Footers go in a final paragraph, one per line, as `Token: value`:
+     - `BREAKING CHANGE: <what breaks and how to migrate>` when existing users must change something; also add `!` before the colon in the first line
+     - `Refs: <issue>` only for issue numbers that appear in the diff
+     - `Co-authored-by: Name <email>` only for co-authors named in the input
 
 Please provide only the commit message without any additional commentary or markdown formatting."#;
 
+const FILE_SUMMARY_PROMPT: &str = r#"You are an expert software developer. Given the git diff of one or more files, summarise for each file in one to three sentences what changed and, where it is apparent, why. Mention added, removed or renamed functions, types and settings by name.
+
+Please provide only the summary as plain text without markdown formatting."#;
+
 const FILE_ANALYSIS_PROMPT: &str = r#"You are an expert software developer tasked with analyzing changes to a file. Given a git diff or file content, you will:
 
 1. Analyze the changes to understand what was modified
@@ -89,4 +517,40 @@ const CONTRIBUTOR_ANALYSIS_PROMPT: &str = r#"You are an expert software develope
    - Any notable patterns or specialties in their work
 
 Format your response in markdown with appropriate headers, lists, and emphasis where relevant.
-Please provide a clear, professional summary that helps understand the contributor's role and impact on the project."#; 
\ No newline at end of file
+Please provide a clear, professional summary that helps understand the contributor's role and impact on the project."#; 
+
+const PULL_REQUEST_PROMPT: &str = r#"You are an expert software developer tasked with writing the description of a pull request. Given the commit messages and the combined diff of a branch, write a description with exactly these sections:
+
+## Summary
+Two or three sentences on what the pull request does and why.
+
+## Changes
+A bullet list of the notable changes, grouped by area where that helps reviewers.
+
+## Testing
+Tests added or changed in the diff, and what a reviewer should check by hand.
+
+## Risks
+What could break: behaviour changes, migrations, configuration or compatibility concerns. Write "None identified." when there are none.
+
+Format your response in markdown and start directly with the first heading.
+Do not include ``` tags in your response unless you are explicitly using them to format code. Do not include ```markdown!
+Describe what the diff shows; do not invent tickets, benchmarks or test results."#;
+
+const RELEASE_SUMMARY_PROMPT: &str = r#"You are an expert software developer tasked with introducing a release to its users. Given the changelog entries of the release, write one short paragraph of two to four sentences that highlights the most important additions and fixes and calls out breaking changes and what users need to do about them.
+
+Please provide only the paragraph as plain text without headings, lists or markdown formatting."#;
+
+const REVIEW_PROMPT: &str = r#"You are an expert software developer reviewing a change to a single file. The diff shows each line's number in the new version of the file in the first column; removed lines have no number.
+
+Report only real problems in the added or changed lines: bugs, security issues, performance problems and, sparingly, style issues that hurt readability. Do not comment on code that is fine, and do not restate what the change does.
+
+Respond with JSON only, in exactly this shape:
+{"findings": [{"start_line": 12, "end_line": 14, "severity": "high", "category": "bug", "message": "What is wrong and why it matters", "suggestion": "How to fix it"}]}
+
+- `start_line` and `end_line` are line numbers from the first column
+- `severity` is one of "low", "medium", "high" or "critical"
+- `category` is one of "bug", "security", "perf" or "style"
+- Return {"findings": []} when there is nothing to report
+
+Do not wrap the JSON in ``` tags or add any other text."#;
@@ -1,16 +1,27 @@
 use std::error::Error;
+use std::path::Path;
 use git2::Repository;
 
 pub mod providers;
 pub mod git_analysis;
 pub mod git;
+pub mod commit;
+pub mod commit_message;
+pub mod changelog;
+pub mod review;
+pub mod changeset;
 pub mod ui;
 pub mod modes;
+pub mod cli;
+pub mod settings;
+pub mod redact;
 
 #[derive(Debug)]
 pub struct Config {
     model: Box<dyn git_analysis::GitAnalyzer>,
     repo_path: String,
+    assume_yes: bool,
+    settings: settings::Settings,
 }
 
 #[derive(Debug)]
@@ -23,21 +34,46 @@ impl Config {
     pub fn new(model: Box<dyn git_analysis::GitAnalyzer>, repo_path: Option<String>) -> Self {
         Self { 
             model,
-            repo_path: repo_path.unwrap_or_else(|| ".".to_string())
+            repo_path: repo_path.unwrap_or_else(|| ".".to_string()),
+            assume_yes: false,
+            settings: settings::Settings::default(),
         }
     }
 
-    pub async fn generate_commit_message(&self, diff: &str) -> Result<String, Box<dyn Error>> {
+    pub fn repo_path(&self) -> &str {
+        &self.repo_path
+    }
+
+    /// Use the persistent configuration for commit types, signing and the like
+    pub fn with_settings(mut self, settings: settings::Settings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Run modes without prompting, accepting the default choice at every step
+    pub fn with_assume_yes(mut self, assume_yes: bool) -> Self {
+        self.assume_yes = assume_yes;
+        self
+    }
+
+    /// Mask secrets in text bound for the provider, or refuse it, as the redaction policy asks
+    pub fn redact(&self, text: &str) -> Result<redact::Redacted, redact::Blocked> {
+        self.settings.redactor().apply(self.settings.redaction_policy(), text)
+    }
+
+    pub async fn generate_commit_message(&self, diff: &str) -> Result<commit_message::CommitMessage, Box<dyn Error>> {
         self.model.generate_commit_message(diff).await
     }
 
-    pub async fn analyze_changes(&self, repo: &Repository) -> Result<Vec<FileAnalysis>, Box<dyn Error>> {
-        let file_diffs = git::get_file_diffs(repo)?;
+    /// Explains each changed file in the working tree, or in the revision or range `rev`
+    pub async fn analyze_changes(&self, repo: &Repository, rev: Option<&str>) -> Result<Vec<FileAnalysis>, Box<dyn Error>> {
+        let file_diffs = git::get_file_diffs(repo, rev, &self.settings.diff_filter())?;
         
         let analysis_futures: Vec<_> = file_diffs.into_iter().map(|(path, diff)| {
             let model = &self.model;
+            let diff = self.redact(&diff);
             async move {
-                let explanation = model.analyze_file_changes(&diff).await?;
+                let explanation = model.analyze_file_changes(&diff?.text).await?;
                 Ok::<FileAnalysis, Box<dyn Error>>(FileAnalysis {
                     path,
                     explanation,
@@ -54,25 +90,123 @@ impl Config {
     pub async fn analyze_contributor(&self, stats: &str) -> Result<String, Box<dyn Error>> {
         self.model.analyze_contributor(stats).await
     }
+
+    pub async fn analyze_file_changes_stream(&self, diff: &str) -> Result<providers::TextStream, Box<dyn Error>> {
+        self.model.analyze_file_changes_stream(diff).await
+    }
+
+    pub async fn analyze_contributor_stream(&self, stats: &str) -> Result<providers::TextStream, Box<dyn Error>> {
+        self.model.analyze_contributor_stream(stats).await
+    }
+
+    pub async fn describe_pull_request_stream(&self, branch: &str) -> Result<providers::TextStream, Box<dyn Error>> {
+        self.model.describe_pull_request_stream(branch).await
+    }
+
+    pub async fn summarize_release(&self, changelog: &str) -> Result<String, Box<dyn Error>> {
+        self.model.summarize_release(changelog).await
+    }
+
+    pub async fn review_file_changes(&self, diff: &str) -> Result<String, Box<dyn Error>> {
+        self.model.review_file_changes(diff).await
+    }
 }
 
-pub async fn run(_repo_path: Option<String>) -> Result<(), Box<dyn Error>> {
-    let repo_path = loop {
-        let path = ui::get_repository_path(".")?;
-        match Repository::open(&path) {
-            Ok(_) => break path,
-            Err(_) => println!("Invalid git repository path. Please try again."),
+/// Analyzer for the primary provider. Models come from the CLI, then the configuration,
+/// then the interactive model picker; task-specific choices win over general ones.
+fn build_analyzer(
+    provider: Box<dyn providers::Provider>,
+    cli: &cli::Cli,
+    settings: &settings::Settings,
+    interactive: bool,
+) -> Result<git_analysis::GitAnalyzerImpl, Box<dyn Error>> {
+    use git_analysis::{Task, TaskModels};
+
+    let task_model = |task: Task| {
+        cli.task_model(task).or_else(|| match cli.model {
+            Some(_) => None,
+            None => settings.task(task).model.clone(),
+        })
+    };
+    let any_task_model = Task::ALL.iter().any(|task| task_model(*task).is_some());
+
+    let models = match cli.model.as_ref().or(settings.model.as_ref()) {
+        Some(model) => TaskModels::uniform(model.as_str()),
+        None if interactive && !any_task_model => ui::select_models(provider.as_ref())?,
+        None => TaskModels::uniform(provider.default_model()),
+    };
+
+    let mut analyzer = git_analysis::GitAnalyzerImpl::new(provider, None).with_models(models);
+    for task in Task::ALL {
+        if let Some(model) = task_model(task) {
+            analyzer = analyzer.with_task_model(task, model);
         }
+    }
+    Ok(settings.configure_analyzer(analyzer))
+}
+
+pub async fn run(cli: cli::Cli) -> Result<(), Box<dyn Error>> {
+    let interactive = !cli.yes;
+    if !interactive && cli.command.is_none() {
+        return Err("--yes requires a subcommand (commit, analyze-files or contributors)".into());
+    }
+
+    let repo_path = match cli.repo.clone() {
+        Some(path) => path,
+        None if interactive && cli.command.is_none() => loop {
+            let path = ui::get_repository_path(".")?;
+            match Repository::open(&path) {
+                Ok(_) => break path,
+                Err(_) => println!("Invalid git repository path. Please try again."),
+            }
+        },
+        None => ".".to_string(),
     };
 
+    let repo = Repository::open(&repo_path)?;
+    // The hook needs no provider, so it is installed before one is chosen
+    if let Some(cli::Command::InstallHook { force }) = cli.command {
+        return modes::install_hook(&repo, force);
+    }
+    let settings = settings::Settings::load(repo.workdir().unwrap_or(Path::new(&repo_path)))?;
+
     let config = {
-        let providers = providers::get_available_providers();
-        let selected_idx = providers::select_provider(&providers)?;
-        Config::new(git_analysis::wrap_provider(providers.into_iter().nth(selected_idx).unwrap()), Some(repo_path))
+        let available = providers::get_available_providers(&settings.http_settings(), &settings.providers);
+        let chain = match cli.provider.as_ref().or(settings.provider.as_ref()) {
+            Some(names) => names
+                .split(',')
+                .map(|name| providers::find_provider(&available, name.trim()))
+                .collect::<Result<Vec<_>, _>>()?,
+            None if interactive => vec![providers::select_provider(&available)?],
+            None => vec![0],
+        };
+
+        // Model choices only apply to the first provider of a fallback chain
+        let mut available: Vec<_> = available.into_iter().map(Some).collect();
+        let mut analyzers = Vec::new();
+        for (position, idx) in chain.into_iter().enumerate() {
+            let provider = available[idx].take().ok_or(providers::ProviderError::InvalidSelection)?;
+            if position == 0 {
+                let analyzer = build_analyzer(provider, &cli, &settings, interactive)?;
+                analyzers.push(Box::new(analyzer) as Box<dyn git_analysis::GitAnalyzer>);
+            } else {
+                let analyzer = git_analysis::GitAnalyzerImpl::new(provider, None);
+                analyzers.push(Box::new(settings.configure_analyzer(analyzer)));
+            }
+        }
+        let analyzer = if analyzers.len() == 1 {
+            analyzers.remove(0)
+        } else {
+            Box::new(git_analysis::FallbackAnalyzer::new(analyzers))
+        };
+        Config::new(analyzer, Some(repo_path))
+            .with_assume_yes(cli.yes)
+            .with_settings(settings)
     };
-    
-    let repo = Repository::open(&config.repo_path)?;
 
+    if let Some(command) = cli.command {
+        return command.into_mode().execute(&config, &repo).await;
+    }
     loop {
         let mode = ui::select_mode().await?;
         mode.execute(&config, &repo).await?;
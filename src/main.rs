@@ -1,11 +1,12 @@
-use dotenv;
-use merit_cli_demo::run;
-use std::env;
+use clap::Parser;
+use merit_cli_demo::{cli::Cli, run};
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() {
     dotenv::dotenv().ok();
     
-    let repo_path = env::args().nth(1);
-    run(repo_path).await
+    if let Err(e) = run(Cli::parse()).await {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
 }
\ No newline at end of file
@@ -1,68 +1,156 @@
+use std::collections::HashMap;
 use std::error::Error;
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use futures::channel::mpsc;
+use futures::{Stream, StreamExt, TryStreamExt};
 use git2::Repository;
 
-use crate::git;
+use crate::commit::{self, CommitOptions};
+use crate::changelog::{self, Release};
+use crate::commit_message::{self, CommitMessage};
+use crate::changeset::ChangeSet;
+use crate::git::{self, MergeDiff};
+use crate::providers::{ProviderError, TextStream, MAX_CONCURRENT_REQUESTS};
+use crate::redact::Blocked;
+use crate::review::{self, ReportFormat, ReviewFinding, Severity};
 use crate::ui;
 use crate::Config;
 
 #[derive(Debug)]
 pub enum Mode {
-    CommitMessage,
-    FileAnalysis,
-    ContributorAnalysis,
+    /// `staged_only` forces the staged-only workflow even when the settings do not ask for it
+    CommitMessage { staged_only: bool, no_verify: bool },
+    InstallHook { force: bool },
+    /// Headless message generation for the prepare-commit-msg hook
+    PrepareCommitMsg { file: PathBuf, source: Option<String> },
+    /// Explain the working tree changes, or those of the commit or range `rev`; `combined` shows
+    /// merge commits as a combined diff instead of against their first parent
+    FileAnalysis { rev: Option<String>, combined: bool },
+    ContributorAnalysis { author: Option<String> },
+    /// Describe the changes `head` would bring into `base`; `None` picks the usual branches
+    PullRequestDescription { base: Option<String>, head: Option<String>, output: Option<PathBuf> },
+    /// Changelog of the commits after `from` up to `to`; `prepend` names the file to add it to
+    Changelog { from: Option<String>, to: Option<String>, summary: bool, prepend: Option<PathBuf> },
+    /// Structured review of the changes; findings at or above `fail_on` make the mode fail
+    Review { staged_only: bool, format: ReportFormat, output: Option<PathBuf>, fail_on: Option<Severity> },
+    /// Propose new messages for the commits after `from` that score below `min_score`;
+    /// commits on remote-tracking branches are only rewritten with `force`
+    Reword { from: Option<String>, min_score: u8, force: bool },
 }
 
 impl Mode {
     pub fn description(&self) -> &'static str {
         match self {
-            Mode::CommitMessage => "📝 Generate commit message",
-            Mode::FileAnalysis => "🔍 Analyze file changes", 
-            Mode::ContributorAnalysis => "👥 Analyze contributors",
+            Mode::CommitMessage { .. } => "📝 Generate commit message",
+            Mode::InstallHook { .. } => "🪝 Install prepare-commit-msg hook",
+            Mode::PrepareCommitMsg { .. } => "📝 Prepare commit message",
+            Mode::FileAnalysis { .. } => "🔍 Analyze file changes",
+            Mode::ContributorAnalysis { .. } => "👥 Analyze contributors",
+            Mode::PullRequestDescription { .. } => "📋 Describe a pull request",
+            Mode::Changelog { .. } => "📜 Generate changelog",
+            Mode::Review { .. } => "🧐 Review changes",
+            Mode::Reword { .. } => "🪄 Reword poor commit messages",
         }
     }
 
     pub async fn execute(&self, config: &Config, repo: &Repository) -> Result<(), Box<dyn Error>> {
-        match self {
-            Mode::CommitMessage => handle_commit_message(config, repo).await,
-            Mode::FileAnalysis => handle_file_analysis(config, repo).await,
-            Mode::ContributorAnalysis => handle_contributor_analysis(config, repo).await,
+        let result = match self {
+            Mode::CommitMessage { staged_only, no_verify } => {
+                let options = CommitOptions { signing: config.settings.signing(), no_verify: *no_verify };
+                handle_commit_message(config, repo, *staged_only || config.settings.staged_only(), &options).await
+            }
+            Mode::InstallHook { force } => install_hook(repo, *force),
+            Mode::PrepareCommitMsg { file, source } => handle_prepare_commit_msg(config, repo, file, source.as_deref()).await,
+            Mode::FileAnalysis { rev, combined } => handle_file_analysis(config, repo, rev.as_deref(), *combined).await,
+            Mode::ContributorAnalysis { author } => handle_contributor_analysis(config, repo, author.as_deref()).await,
+            Mode::PullRequestDescription { base, head, output } => {
+                handle_pull_request(config, repo, base.as_deref(), head.as_deref(), output.as_deref()).await
+            }
+            Mode::Changelog { from, to, summary, prepend } => {
+                handle_changelog(config, repo, from.as_deref(), to.as_deref(), *summary, prepend.as_deref()).await
+            }
+            Mode::Review { staged_only, format, output, fail_on } => {
+                handle_review(config, repo, *staged_only, *format, output.as_deref(), *fail_on).await
+            }
+            Mode::Reword { from, min_score, force } => handle_reword(config, repo, from.as_deref(), *min_score, *force).await,
+        };
+
+        // In interactive sessions explain provider failures and go back to the menu
+        match result {
+            Err(e) if !config.assume_yes => match e.downcast_ref::<ProviderError>() {
+                Some(provider_error) => {
+                    ui::print_provider_error(provider_error);
+                    Ok(())
+                }
+                // The report was already shown
+                None if e.is::<Blocked>() => Ok(()),
+                None => Err(e),
+            },
+            result => result,
         }
     }
 }
 
-async fn handle_commit_message(config: &Config, repo: &Repository) -> Result<(), Box<dyn Error>> {
-    match git::get_diff(repo) {
-        Ok(diff) => {
+async fn handle_commit_message(
+    config: &Config,
+    repo: &Repository,
+    staged_only: bool,
+    options: &CommitOptions,
+) -> Result<(), Box<dyn Error>> {
+    let scope = if staged_only {
+        if !config.assume_yes {
+            pick_changes_to_stage(repo)?;
+        }
+        git::DiffScope::Staged
+    } else {
+        git::DiffScope::WorkingTree
+    };
+    let commit = |message: &str| {
+        if staged_only {
+            git::commit_staged(repo, message, options)
+        } else {
+            git::stage_and_commit(repo, message, options)
+        }
+    };
+
+    match git::get_changes(repo, scope) {
+        Ok(changes) => {
+            let scopes = commit_message::infer_scopes(changes.files.iter().map(|file| file.path.as_str()));
+            let diff = redact_for_prompt(config, &changes.filtered(&config.settings.diff_filter()).render())?;
+            let mut commit_message = generate_with_spinner(config, &diff).await?;
+            if config.assume_yes {
+                commit(&commit_message.to_string())?;
+                println!("Changes committed successfully!");
+                return Ok(());
+            }
+
             loop {
-                let commit_message = generate_with_spinner(config, &diff).await?;
-                
                 let options = [
                     "✨ Regenerate message",
-                    "📝 Edit commit type",
-                    "✅ Stage and commit",
+                    "📝 Edit type, scope and breaking change",
+                    "✏️ Edit in editor",
+                    if staged_only { "✅ Commit staged changes" } else { "✅ Stage and commit" },
                     "❌ Cancel"
                 ];
                 
-                match ui::show_selection_menu("What would you like to do?", &options, 2)? {
-                    0 => continue, // Regenerate
+                match ui::show_selection_menu("What would you like to do?", &options, 3)? {
+                    0 => commit_message = generate_with_spinner(config, &diff).await?,
                     1 => {
-                        let types = [
-                            "feat: ✨ New feature",
-                            "fix: 🐛 Bug fix", 
-                            "docs: 📚 Documentation",
-                            "style: 💅 Formatting",
-                            "refactor: ♻️ Code restructure",
-                            "test: 🧪 Testing",
-                            "chore: 🔧 Maintenance",
-                        ];
+                        let commit_types = config.settings.commit_types();
+                        let types: Vec<String> = commit_types.iter()
+                            .map(|t| format!("{}: {}", t.name, t.description))
+                            .collect();
                         
-                        let type_idx = ui::show_selection_menu("Select commit type", &types, 0)?;
-                        let selected_type = types[type_idx].split(':').next().unwrap();
-                        let description = commit_message.split(':').nth(1).unwrap_or(&commit_message).trim();
-                        let new_message = format!("{}: {}", selected_type, description);
+                        let current = commit_types.iter().position(|t| t.name == commit_message.commit_type).unwrap_or(0);
+                        let type_idx = ui::show_selection_menu("Select commit type", &types, current)?;
+                        commit_message.commit_type = commit_types[type_idx].name.clone();
+                        commit_message.scope = pick_scope(config, &scopes, commit_message.scope.as_deref())?;
+                        commit_message.set_breaking(ui::confirm("Is this a breaking change?", commit_message.breaking)?);
                         
                         ui::print_section("📝 New Commit Message");
-                        println!("{}\n", new_message);
+                        println!("{}\n", commit_message);
 
                         let confirm_options = [
                             "✅ Confirm and commit", 
@@ -71,16 +159,23 @@ async fn handle_commit_message(config: &Config, repo: &Repository) -> Result<(),
                         ];
                         match ui::show_selection_menu("Would you like to proceed with this commit message?", &confirm_options, 0)? {
                             0 => {
-                                git::stage_and_commit(repo, &new_message)?;
+                                commit(&commit_message.to_string())?;
                                 println!("Changes committed successfully!");
                                 break;
                             }
-                            1 => continue,
+                            1 => commit_message = generate_with_spinner(config, &diff).await?,
                             _ => break,
                         }
                     }
                     2 => {
-                        git::stage_and_commit(repo, &commit_message)?;
+                        if let Some(edited) = edit_until_valid(config, repo, &commit_message)? {
+                            commit_message = edited;
+                            ui::print_section("📝 Edited Commit Message");
+                            println!("{}\n", commit_message);
+                        }
+                    }
+                    3 => {
+                        commit(&commit_message.to_string())?;
                         println!("Changes committed successfully!");
                         break;
                     }
@@ -92,7 +187,11 @@ async fn handle_commit_message(config: &Config, repo: &Repository) -> Result<(),
         Err(e) => {
             if e.to_string() == "No changes to commit" {
                 ui::print_section("📝 Repository Status");
-                println!("No changes to commit. Your working directory is clean.\n");
+                if staged_only {
+                    println!("No staged changes to commit. Stage some changes first.\n");
+                } else {
+                    println!("No changes to commit. Your working directory is clean.\n");
+                }
                 Ok(())
             } else {
                 Err(e)
@@ -101,36 +200,205 @@ async fn handle_commit_message(config: &Config, repo: &Repository) -> Result<(),
     }
 }
 
-async fn handle_file_analysis(config: &Config, repo: &Repository) -> Result<(), Box<dyn Error>> {
-    let spinner = ui::create_spinner("Analyzing changes")?;
-    let result = config.analyze_changes(repo).await;
-    spinner.finish_and_clear();
-    
-    match result {
-        Ok(analyses) => {
-            ui::print_section("📊 File Analysis Results");
-            
-            for analysis in analyses {
-                let markdown = format!("## 📁 {}\n{}", analysis.path, analysis.explanation);
-                ui::print_markdown(&markdown);
+/// Lets the user adjust the index by file or by hunk before the staged changes are described
+fn pick_changes_to_stage(repo: &Repository) -> Result<(), Box<dyn Error>> {
+    let changes = git::get_file_changes(repo)?;
+    if !changes.iter().any(|change| change.unstaged) {
+        return Ok(());
+    }
+
+    let options = [
+        "📋 Use the staged changes",
+        "📁 Pick files",
+        "✂️ Pick hunks",
+    ];
+    match ui::show_selection_menu("Which changes should be committed?", &options, 0)? {
+        1 => {
+            let items: Vec<String> = changes.iter().map(|change| {
+                match (change.staged, change.unstaged) {
+                    (true, true) => format!("{} (partly staged, keeps only the staged part)", change.path),
+                    _ => change.path.clone(),
+                }
+            }).collect();
+            let checked: Vec<bool> = changes.iter().map(|change| change.staged).collect();
+            let selection = ui::show_multi_selection("Select the files to commit (space to toggle)", &items, &checked)?;
+            let selected: Vec<&str> = selection.iter().map(|&idx| changes[idx].path.as_str()).collect();
+            // Staging the rest of a partly staged file would discard its hunk selection, so only on request
+            let mut keep_index = Vec::new();
+            for change in selection.iter().map(|&idx| &changes[idx]).filter(|change| change.staged && change.unstaged) {
+                if !ui::confirm(&format!("Also stage the unstaged changes of {}?", change.path), false)? {
+                    keep_index.push(change.path.as_str());
+                }
             }
-            
-            Ok(())
+            git::stage_files(repo, &changes, &selected, &keep_index)?;
         }
-        Err(e) => {
-            if e.to_string() == "No changes to commit" {
-                ui::print_section("📊 Repository Status");
-                println!("No changes to analyze. Your working directory is clean.\n");
-                Ok(())
-            } else {
-                Err(e)
+        2 => {
+            let changes = git::get_unstaged_changes(repo)?;
+            let total: usize = changes.files.iter()
+                .map(|file| if git::is_staged_whole(file) { 1 } else { file.hunks.len() })
+                .sum();
+            let mut shown = 0;
+            let mut selected = Vec::new();
+            for file in &changes.files {
+                if git::is_staged_whole(file) {
+                    shown += 1;
+                    ui::print_subsection(&format!("📄 {} ({}/{})", file.path, shown, total));
+                    println!("{} file, staged as a whole", if file.binary { "binary" } else { file.status.label() });
+                    if ui::confirm("Stage this file?", false)? {
+                        selected.push(file.clone());
+                    }
+                    continue;
+                }
+
+                let mut chosen = file.clone();
+                chosen.hunks.clear();
+                for hunk in &file.hunks {
+                    shown += 1;
+                    ui::print_subsection(&format!("📄 {} ({}/{})", file.path, shown, total));
+                    println!("{}", hunk.header);
+                    println!("{}", hunk.body().trim_end());
+                    if ui::confirm("Stage this hunk?", false)? {
+                        chosen.hunks.push(hunk.clone());
+                    }
+                }
+                if !chosen.hunks.is_empty() {
+                    selected.push(chosen);
+                }
             }
+            git::stage_hunks(repo, &selected)?;
         }
+        _ => {}
     }
+    Ok(())
 }
 
-async fn handle_contributor_analysis(config: &Config, repo: &Repository) -> Result<(), Box<dyn Error>> {
-    let contributors = git::get_contributors(repo)?;
+async fn handle_file_analysis(config: &Config, repo: &Repository, rev: Option<&str>, combined: bool) -> Result<(), Box<dyn Error>> {
+    let rev = match rev {
+        Some(rev) => Some(rev.to_string()),
+        None if config.assume_yes => None,
+        None => {
+            let options = ["📂 Working directory", "🔖 A commit or range of commits"];
+            match ui::show_selection_menu("Which changes should be analyzed?", &options, 0)? {
+                0 => None,
+                _ => Some(ui::input("Commit or range, such as HEAD~5..HEAD", "HEAD")?.trim().to_string()),
+            }
+        }
+    };
+
+    let changes = match rev.as_deref() {
+        Some(rev) => {
+            let changes = revision_changes(config, repo, rev, combined)?.filtered(&config.settings.diff_filter());
+            if changes.files.is_empty() {
+                println!("No file changes to analyze in {}.\n", rev);
+                return Ok(());
+            }
+            changes
+        }
+        None => match git::get_changes(repo, git::DiffScope::WorkingTree) {
+            Ok(changes) => changes.filtered(&config.settings.diff_filter()),
+            Err(e) => {
+                if e.to_string() == "No changes to commit" {
+                    ui::print_section("📊 Repository Status");
+                    println!("No changes to analyze. Your working directory is clean.\n");
+                    return Ok(());
+                }
+                return Err(e);
+            }
+        },
+    };
+
+    let diffs = changes.files.iter()
+        .filter(|file| file.omitted.is_none())
+        .map(|file| redact_for_prompt(config, &file.render()))
+        .collect::<Result<Vec<_>, _>>()?;
+    // Each answer streams into its own channel, a few requests at a time, so the later requests
+    // keep making progress while the earlier answers are shown in file order
+    let (senders, receivers): (Vec<_>, Vec<_>) = diffs.iter().map(|_| mpsc::unbounded()).unzip();
+    let requests = futures::stream::iter(diffs.iter().zip(senders))
+        .for_each_concurrent(MAX_CONCURRENT_REQUESTS, |(diff, sender)| async move {
+            // Rendering stopped on an error, so the answer would not be shown
+            if sender.is_closed() {
+                return;
+            }
+            match config.analyze_file_changes_stream(diff).await {
+                Ok(mut stream) => {
+                    while let Some(chunk) = stream.next().await {
+                        if sender.unbounded_send(chunk.map_err(|e| e as Box<dyn Error>)).is_err() {
+                            break;
+                        }
+                    }
+                }
+                Err(e) => {
+                    let _ = sender.unbounded_send(Err(e));
+                }
+            }
+        });
+    let render = async {
+        ui::print_section("📊 File Analysis Results");
+
+        let mut analyses = receivers.into_iter();
+        for file in &changes.files {
+            ui::print_markdown(&format!("## 📁 {}", file.path));
+            if let Some(reason) = &file.omitted {
+                println!("Changed, content omitted ({})\n", reason);
+                continue;
+            }
+            let analysis = analyses.next().ok_or("Missing file analysis")?;
+            show_markdown("Analyzing changes", analysis).await?;
+        }
+        Ok::<_, Box<dyn Error>>(())
+    };
+    futures::future::join(requests, render).await.1?;
+    report_provider(config);
+    
+    Ok(())
+}
+
+/// Changes of a commit or range, after listing its commits. A merge commit is compared with its
+/// first parent unless `combined` asks, or the user chooses, for a combined diff.
+fn revision_changes(config: &Config, repo: &Repository, rev: &str, combined: bool) -> Result<ChangeSet, Box<dyn Error>> {
+    const LISTED_COMMITS: usize = 20;
+
+    let mut range = git::get_revision(repo, rev, MergeDiff::FirstParent)?;
+    let is_merge = range.commits.len() == 1 && range.commits[0].merge;
+    let combined = is_merge && (combined || (!config.assume_yes && ui::confirm(
+        "This is a merge commit. Show only what the merge changed beyond its parents (combined diff)?",
+        false,
+    )?));
+    if combined {
+        range = git::get_revision(repo, rev, MergeDiff::Combined)?;
+    }
+
+    ui::print_section("🔖 Commits");
+    for commit in range.commits.iter().take(LISTED_COMMITS) {
+        println!("{} {}", commit.id, commit.message.lines().next().unwrap_or_default());
+    }
+    if range.commits.len() > LISTED_COMMITS {
+        println!("... and {} more", range.commits.len() - LISTED_COMMITS);
+    }
+    if is_merge {
+        println!("\n{}", if combined {
+            "Merge commit: only files that differ from every parent, diffed against each of them."
+        } else {
+            "Merge commit: compared with its first parent. Use --combined for a combined diff."
+        });
+    }
+    println!();
+    Ok(range.changes)
+}
+
+async fn handle_contributor_analysis(config: &Config, repo: &Repository, author: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let mut contributors = git::get_contributors(repo)?;
+    if let Some(author) = author {
+        contributors.retain(|c| c.name.contains(author) || c.email.contains(author));
+    }
+
+    if config.assume_yes {
+        for contributor in &contributors {
+            analyze_contributor(config, repo, contributor).await?;
+        }
+        return Ok(());
+    }
     
     ui::print_section("👥 Repository Contributors");
     
@@ -146,16 +414,7 @@ async fn handle_contributor_analysis(config: &Config, repo: &Repository) -> Resu
             break;
         }
 
-        let contributor = &contributors[selection];
-        display_contributor_info(contributor);
-        
-        let stats = format_contributor_stats(contributor, repo)?;
-        let spinner = ui::create_spinner("Analyzing contributor's work")?;
-        let summary = config.analyze_contributor(&stats).await?;
-        spinner.finish_and_clear();
-        
-        ui::print_section("🤖 AI Analysis");
-        ui::print_markdown(&summary);
+        analyze_contributor(config, repo, &contributors[selection]).await?;
 
         println!("\nPress Enter to continue...");
         std::io::stdin().read_line(&mut String::new())?;
@@ -165,17 +424,566 @@ async fn handle_contributor_analysis(config: &Config, repo: &Repository) -> Resu
     Ok(())
 }
 
-async fn generate_with_spinner(config: &Config, diff: &str) -> Result<String, Box<dyn Error>> {
+async fn analyze_contributor(
+    config: &Config,
+    repo: &Repository,
+    contributor: &git::ContributorStats,
+) -> Result<(), Box<dyn Error>> {
+    display_contributor_info(contributor);
+    
+    // Holds the author's email and raw commit messages, which may carry secrets
+    let stats = redact_for_prompt(config, &format_contributor_stats(contributor, repo)?)?;
+    
+    ui::print_section("🤖 AI Analysis");
+    stream_markdown("Analyzing contributor's work", config.analyze_contributor_stream(&stats)).await?;
+    report_provider(config);
+    Ok(())
+}
+
+async fn handle_pull_request(
+    config: &Config,
+    repo: &Repository,
+    base: Option<&str>,
+    head: Option<&str>,
+    output: Option<&Path>,
+) -> Result<(), Box<dyn Error>> {
+    let base = match base {
+        Some(base) => base.to_string(),
+        None if config.assume_yes => git::default_base_branch(repo).ok_or("Could not find a base branch. Pass it with --base")?,
+        None => ui::input("Base branch", git::default_base_branch(repo).as_deref().unwrap_or("main"))?,
+    };
+    let current = repo.head().ok().and_then(|head| head.shorthand().map(str::to_string)).unwrap_or_else(|| "HEAD".to_string());
+    let head = match head {
+        Some(head) => head.to_string(),
+        None if config.assume_yes => current,
+        None => ui::input("Head branch", &current)?,
+    };
+
+    let range = git::get_range(repo, &base, &head)?;
+    let input = format!(
+        "Pull request from `{}` into `{}` ({} commits)\n\n{}",
+        head,
+        base,
+        range.commits.len(),
+        range.render(&config.settings.diff_filter())
+    );
+    let input = redact_for_prompt(config, &input)?;
+
+    if config.assume_yes {
+        let description = collect_stream(config.describe_pull_request_stream(&input)).await?;
+        match output {
+            Some(path) => {
+                fs::write(path, &description)?;
+                eprintln!("💾 Wrote the pull request description to {}", path.display());
+            }
+            None => println!("{}", description.trim_end()),
+        }
+        return Ok(());
+    }
+
+    loop {
+        ui::print_section(&format!("📋 Pull Request: {} → {}", head, base));
+        let description = stream_markdown("Describing the pull request", config.describe_pull_request_stream(&input)).await?;
+        report_provider(config);
+
+        loop {
+            let options = ["💾 Save to file", "📄 Print as plain markdown", "✨ Regenerate", "✅ Done"];
+            match ui::show_selection_menu("What would you like to do?", &options, 0)? {
+                0 => {
+                    let default = output.map_or("PULL_REQUEST.md".to_string(), |path| path.display().to_string());
+                    let path = ui::input("File", &default)?;
+                    fs::write(&path, &description)?;
+                    println!("💾 Wrote {}\n", path);
+                }
+                1 => println!("\n{}\n", description.trim_end()),
+                2 => break,
+                _ => return Ok(()),
+            }
+        }
+    }
+}
+
+async fn handle_changelog(
+    config: &Config,
+    repo: &Repository,
+    from: Option<&str>,
+    to: Option<&str>,
+    summary: bool,
+    prepend: Option<&Path>,
+) -> Result<(), Box<dyn Error>> {
+    let to = match to {
+        Some(to) => to.to_string(),
+        None if config.assume_yes => "HEAD".to_string(),
+        None => ui::input("Last commit or tag of the release", "HEAD")?,
+    };
+    // A tag names the release; anything else collects unreleased changes since the latest tag
+    let is_release = git::is_tag(repo, &to);
+    let from = match from {
+        Some(from) => Some(from.to_string()),
+        None => {
+            let latest = git::latest_tag(repo, &to, is_release)?;
+            if config.assume_yes {
+                latest
+            } else {
+                Some(ui::input("Previous tag (empty for the whole history)", latest.as_deref().unwrap_or_default())?)
+                    .filter(|from| !from.trim().is_empty())
+            }
+        }
+    };
+
+    let commits = git::get_commits_between(repo, from.as_deref(), &to)?;
+    let release = if is_release {
+        Release::new(Some(&to), Some(&git::commit_date(repo, &to)?), &commits)
+    } else {
+        Release::new(None, None, &commits)
+    };
+    if release.is_empty() {
+        println!("No changelog entries between {} and {}: none of the {} commits is a feature, fix or breaking change.",
+            from.as_deref().unwrap_or("the first commit"), to, commits.len());
+        return Ok(());
+    }
+
+    let summary = summary || (!config.assume_yes && ui::confirm("Add a release summary written by the model?", false)?);
+    let release = if summary {
+        let entries = redact_for_prompt(config, &release.entries_text())?;
+        let spinner = ui::create_spinner("Summarising the release")?;
+        let text = config.summarize_release(&entries).await;
+        spinner.finish_and_clear();
+        release.with_summary(text?)
+    } else {
+        release
+    };
+
+    let save = |path: &Path| -> Result<(), Box<dyn Error>> {
+        let existing = fs::read_to_string(path).ok();
+        fs::write(path, changelog::prepend(existing.as_deref(), &release))?;
+        println!("💾 Added {} to {}", release.heading().trim_start_matches("## "), path.display());
+        Ok(())
+    };
+
+    if config.assume_yes {
+        return match prepend {
+            Some(path) => save(path),
+            None => {
+                print!("{}", release.render());
+                Ok(())
+            }
+        };
+    }
+
+    ui::print_section("📜 Changelog");
+    ui::print_markdown(&release.render());
+    if release.skipped > 0 {
+        println!("{} merges, non-conventional commits and changes such as docs or ci were left out.\n", release.skipped);
+    }
+    loop {
+        let target = prepend.unwrap_or(Path::new("CHANGELOG.md"));
+        let options = [format!("💾 Prepend to {}", target.display()), "📄 Print as plain markdown".to_string(), "✅ Done".to_string()];
+        match ui::show_selection_menu("What would you like to do?", &options, 0)? {
+            0 => {
+                save(target)?;
+                return Ok(());
+            }
+            1 => println!("\n{}", release.render()),
+            _ => return Ok(()),
+        }
+    }
+}
+
+async fn handle_review(
+    config: &Config,
+    repo: &Repository,
+    staged_only: bool,
+    format: ReportFormat,
+    output: Option<&Path>,
+    fail_on: Option<Severity>,
+) -> Result<(), Box<dyn Error>> {
+    let scope = if staged_only { git::DiffScope::Staged } else { git::DiffScope::WorkingTree };
+    let changes = match git::get_changes(repo, scope) {
+        Ok(changes) => changes.filtered(&config.settings.diff_filter()),
+        Err(e) if e.to_string() == "No changes to commit" => {
+            println!("No changes to review.");
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    let files: Vec<_> = changes.files.iter().filter(|file| file.omitted.is_none() && !file.hunks.is_empty()).collect();
+
+    let mut prompts = Vec::new();
+    for file in &files {
+        prompts.push(redact_for_prompt(config, &review::annotate(file))?);
+    }
+    let spinner = ui::create_spinner(&format!("Reviewing {} file(s)", files.len()))?;
+    let answers: Vec<_> = futures::stream::iter(prompts.iter())
+        .map(|prompt| config.review_file_changes(prompt))
+        .buffered(MAX_CONCURRENT_REQUESTS)
+        .collect()
+        .await;
+    spinner.finish_and_clear();
+
+    // A file whose review failed or cannot be read is reported, and fails a severity gate
+    let mut findings: Vec<ReviewFinding> = Vec::new();
+    let mut unreviewed = 0;
+    for (file, answer) in files.iter().zip(answers) {
+        match answer.and_then(|answer| review::parse_findings(&answer, file)) {
+            Ok(file_findings) => findings.extend(file_findings),
+            Err(e) => {
+                eprintln!("⚠️ Could not review {}: {}", file.path, e);
+                unreviewed += 1;
+            }
+        }
+    }
+    if unreviewed > 0 && unreviewed == files.len() {
+        return Err(format!("None of the {} file(s) could be reviewed", files.len()).into());
+    }
+
+    let report = match format {
+        ReportFormat::Text => review::render_text(&findings),
+        ReportFormat::Json => review::render_json(&findings)?,
+        ReportFormat::Sarif => review::render_sarif(&findings)?,
+    };
+    match output {
+        Some(path) => {
+            fs::write(path, &report)?;
+            println!("💾 Wrote {} finding(s) to {}", findings.len(), path.display());
+        }
+        None if format == ReportFormat::Text => {
+            ui::print_section("🧐 Review");
+            print!("{}", report);
+            report_provider(config);
+        }
+        None => println!("{}", report),
+    }
+
+    if let Some(threshold) = fail_on {
+        let failing = review::count_at_least(&findings, threshold);
+        let mut reasons = Vec::new();
+        if failing > 0 {
+            reasons.push(format!("{} finding(s) at or above {} severity", failing, threshold));
+        }
+        if unreviewed > 0 {
+            reasons.push(format!("{} file(s) could not be reviewed", unreviewed));
+        }
+        if !reasons.is_empty() {
+            return Err(reasons.join(", ").into());
+        }
+    }
+    Ok(())
+}
+
+/// Messages scoring below this are reworded unless `--min-score` says otherwise
+pub const DEFAULT_MIN_SCORE: u8 = 70;
+
+async fn handle_reword(
+    config: &Config,
+    repo: &Repository,
+    from: Option<&str>,
+    min_score: u8,
+    force: bool,
+) -> Result<(), Box<dyn Error>> {
+    if repo.state() != git2::RepositoryState::Clean {
+        return Err("Finish the merge, rebase or other operation in progress before rewording commits".into());
+    }
+    let from = match from {
+        Some(from) => Some(from.to_string()),
+        None if config.assume_yes => Some(git::upstream_branch(repo)
+            .ok_or("The branch has no upstream. Pass the last commit to keep with --from")?),
+        None => {
+            let default = git::upstream_branch(repo).unwrap_or_else(|| "HEAD~10".to_string());
+            Some(ui::input("Reword the commits after (empty for the whole history)", &default)?)
+                .filter(|from| !from.trim().is_empty())
+        }
+    };
+
+    let commits = git::commits_after(repo, from.as_deref())?;
+    let allowed_types: Vec<String> = config.settings.commit_types().into_iter().map(|t| t.name).collect();
+    // Merge commits keep the messages git wrote for them
+    let candidates: Vec<_> = commits.iter()
+        .filter(|commit| commit.parent_count() < 2)
+        .map(|commit| (commit, CommitMessage::parse(commit.message().unwrap_or_default()).score(&allowed_types)))
+        .filter(|(_, score)| score.score < min_score)
+        .collect();
+
+    ui::print_section("🧮 Commit Message Scores");
+    if candidates.is_empty() {
+        println!("All {} commit(s) score {} or more. Nothing to reword.\n", commits.len(), min_score);
+        return Ok(());
+    }
+    for (commit, score) in &candidates {
+        println!("{} {:>3}  {}", short_id(commit)?, score.score, commit.summary().unwrap_or_default());
+        for problem in &score.problems {
+            println!("             • {}", problem);
+        }
+    }
+    println!("\n{} of {} commit(s) score below {}.\n", candidates.len(), commits.len(), min_score);
+
+    let ids: Vec<git2::Oid> = candidates.iter().map(|(commit, _)| commit.id()).collect();
+    let pushed = git::pushed_commits(repo, &ids)?;
+    if !pushed.is_empty() {
+        if !force {
+            return Err(format!(
+                "{} of these commits are already on a remote-tracking branch, and rewording them would rewrite published history. Pass --force to reword them anyway",
+                pushed.len()
+            ).into());
+        }
+        println!("⚠️ {} of these commits were already pushed. Rewording them means force-pushing the branch.\n", pushed.len());
+    }
+
+    let filter = config.settings.diff_filter();
+    let mut messages = HashMap::new();
+    'commits: for (index, (commit, _)) in candidates.iter().enumerate() {
+        let original = commit.message().unwrap_or_default().trim_end();
+        ui::print_section(&format!("✏️ Commit {} of {}: {}", index + 1, candidates.len(), short_id(commit)?));
+        println!("{}\n", original);
+
+        let changes = git::get_revision(repo, &commit.id().to_string(), MergeDiff::FirstParent)?.changes.filtered(&filter);
+        if changes.files.is_empty() {
+            println!("The commit changes no files, keeping its message.\n");
+            continue;
+        }
+        let prompt = redact_for_prompt(config, &format!(
+            "The commit was originally described as:\n{}\n\n{}",
+            original,
+            changes.render()
+        ))?;
+        let mut message = generate_with_spinner(config, &prompt).await?;
+        // Without a prompt nobody approves the message, so it is only shown
+        if config.assume_yes {
+            continue;
+        }
+
+        loop {
+            let options = [
+                "✅ Use this message",
+                "✨ Regenerate message",
+                "✏️ Edit in editor",
+                "⏭️ Keep the original message",
+                "🛑 Stop and rewrite the messages approved so far",
+            ];
+            match ui::show_selection_menu("What would you like to do?", &options, 0)? {
+                0 => {
+                    messages.insert(commit.id(), message.to_string());
+                    break;
+                }
+                1 => message = generate_with_spinner(config, &prompt).await?,
+                2 => {
+                    if let Some(edited) = edit_until_valid(config, repo, &message)? {
+                        message = edited;
+                        ui::print_section("📝 Edited Commit Message");
+                        println!("{}\n", message);
+                    }
+                }
+                3 => break,
+                _ => break 'commits,
+            }
+        }
+    }
+
+    if config.assume_yes {
+        println!("Nothing was rewritten. Run without --yes to approve each message.\n");
+        return Ok(());
+    }
+    if messages.is_empty() {
+        println!("No commit messages were changed.\n");
+        return Ok(());
+    }
+    let prompt = format!("Rewrite {} commit message(s)? The commits after them get new ids too.", messages.len());
+    if !ui::confirm(&prompt, true)? {
+        println!("Nothing was rewritten.\n");
+        return Ok(());
+    }
+
+    let previous = repo.head()?.peel_to_commit()?;
+    let from_id = match from.as_deref() {
+        Some(from) => Some(repo.revparse_single(from)?.peel_to_commit()?.id()),
+        None => None,
+    };
+    let tip = commit::reword_commits(repo, from_id, &messages, config.settings.signing())?;
+    println!("✅ Reworded {} commit(s), HEAD is now {}.", messages.len(), short_id(&repo.find_commit(tip)?)?);
+    println!("To undo, run `git reset --soft {}`.\n", previous.id());
+    Ok(())
+}
+
+fn short_id(commit: &git2::Commit) -> Result<String, Box<dyn Error>> {
+    Ok(commit.as_object().short_id()?.as_str().unwrap_or_default().to_string())
+}
+
+
+/// Applies the redaction policy and reports what was masked or why the request was blocked
+fn redact_for_prompt(config: &Config, text: &str) -> Result<String, Box<dyn Error>> {
+    match config.redact(text) {
+        Ok(redacted) => {
+            if !redacted.findings.is_empty() {
+                ui::print_redaction_report(&redacted.findings, false);
+            }
+            Ok(redacted.text)
+        }
+        Err(blocked) => {
+            ui::print_redaction_report(&blocked.findings, true);
+            Err(Box::new(blocked))
+        }
+    }
+}
+
+/// Shows a spinner until the first chunk arrives, then renders the markdown as it streams in.
+/// Returns the full text.
+async fn stream_markdown(
+    spinner_message: &str,
+    request: impl Future<Output = Result<TextStream, Box<dyn Error>>>,
+) -> Result<String, Box<dyn Error>> {
+    let chunks = futures::stream::once(request)
+        .map_ok(|stream| stream.map_err(|e| e as Box<dyn Error>))
+        .try_flatten();
+    show_markdown(spinner_message, chunks).await
+}
+
+/// Renders chunks that are already being requested, as `stream_markdown` does
+async fn show_markdown(
+    spinner_message: &str,
+    chunks: impl Stream<Item = Result<String, Box<dyn Error>>>,
+) -> Result<String, Box<dyn Error>> {
+    let spinner = ui::create_spinner(spinner_message)?;
+    let mut chunks = std::pin::pin!(chunks);
+
+    let mut markdown: Option<ui::MarkdownStream> = None;
+    let mut text = String::new();
+    while let Some(chunk) = chunks.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                spinner.finish_and_clear();
+                return Err(e);
+            }
+        };
+        markdown
+            .get_or_insert_with(|| {
+                spinner.finish_and_clear();
+                ui::MarkdownStream::new()
+            })
+            .push(&chunk);
+        text.push_str(&chunk);
+    }
+
+    spinner.finish_and_clear();
+    if let Some(markdown) = markdown {
+        markdown.finish();
+    }
+    Ok(text)
+}
+
+/// Collects a streamed answer without printing it
+async fn collect_stream(request: impl Future<Output = Result<TextStream, Box<dyn Error>>>) -> Result<String, Box<dyn Error>> {
+    let mut stream = request.await?;
+    let mut text = String::new();
+    while let Some(chunk) = stream.next().await {
+        text.push_str(&chunk.map_err(|e| e as Box<dyn Error>)?);
+    }
+    Ok(text)
+}
+
+async fn generate_with_spinner(config: &Config, diff: &str) -> Result<CommitMessage, Box<dyn Error>> {
     let spinner = ui::create_spinner("Generating commit message")?;
     let commit_message = config.generate_commit_message(diff).await?;
     spinner.finish_and_clear();
 
     ui::print_section("📝 Generated Commit Message");
     println!("{}\n", commit_message);
+    report_provider(config);
     
     Ok(commit_message)
 }
 
+pub fn install_hook(repo: &Repository, force: bool) -> Result<(), Box<dyn Error>> {
+    let path = commit::install_prepare_commit_msg_hook(repo, force)?;
+    println!("🪝 Installed {}", path.display());
+    println!("`git commit` now suggests a message for the staged changes. Merges, amends and messages given with -m or -F are left alone.");
+    Ok(())
+}
+
+/// Write a message for the staged changes ahead of the comments git put in `file`
+async fn handle_prepare_commit_msg(config: &Config, repo: &Repository, file: &Path, source: Option<&str>) -> Result<(), Box<dyn Error>> {
+    // Messages from -m/-F, merges, squashes and amends are the user's own
+    if matches!(source, Some("message" | "merge" | "squash" | "commit")) {
+        return Ok(());
+    }
+    let existing = fs::read_to_string(file)?;
+    if !commit_message::strip_comments(&existing).is_empty() {
+        return Ok(());
+    }
+
+    // `git commit -a` and `git commit <paths>` stage into a temporary index
+    if let Some(index_path) = std::env::var_os("GIT_INDEX_FILE") {
+        repo.set_index(&mut git2::Index::open(Path::new(&index_path))?)?;
+    }
+    let changes = match git::get_changes(repo, git::DiffScope::Staged) {
+        Ok(changes) => changes,
+        Err(_) => return Ok(()),
+    };
+    let diff = redact_for_prompt(config, &changes.filtered(&config.settings.diff_filter()).render())?;
+    let message = config.generate_commit_message(&diff).await?;
+    fs::write(file, format!("{}\n{}", message, existing))?;
+    Ok(())
+}
+
+/// Choose among the inferred and configured scopes, no scope, or one typed in
+fn pick_scope(config: &Config, inferred: &[String], current: Option<&str>) -> Result<Option<String>, Box<dyn Error>> {
+    let mut scopes: Vec<String> = Vec::new();
+    for scope in current.into_iter().map(str::to_string).chain(inferred.iter().cloned()).chain(config.settings.commit_scopes().iter().cloned()) {
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+
+    let mut options = scopes.clone();
+    options.push("(no scope)".to_string());
+    options.push("✏️ Enter a scope".to_string());
+    let default = if current.is_none() && inferred.is_empty() { scopes.len() } else { 0 };
+    let selection = ui::show_selection_menu("Select commit scope", &options, default)?;
+    Ok(if selection < scopes.len() {
+        Some(scopes[selection].clone())
+    } else if selection == scopes.len() {
+        None
+    } else {
+        Some(ui::input("Scope", current.unwrap_or_default())?.trim().to_string()).filter(|scope| !scope.is_empty())
+    })
+}
+
+/// Open the message in the user's editor until it follows the commit conventions.
+/// Returns `None` when the edit is abandoned, keeping the previous message.
+fn edit_until_valid(config: &Config, repo: &Repository, message: &CommitMessage) -> Result<Option<CommitMessage>, Box<dyn Error>> {
+    let allowed_types: Vec<String> = config.settings.commit_types().into_iter().map(|t| t.name).collect();
+    let mut text = message.to_string();
+    loop {
+        text = commit::edit_message(repo, &text)?;
+        if text.is_empty() {
+            println!("Empty commit message, keeping the previous one.\n");
+            return Ok(None);
+        }
+
+        let edited = CommitMessage::parse(&text);
+        let problems = edited.validate(&allowed_types);
+        if problems.is_empty() {
+            return Ok(Some(edited));
+        }
+        ui::print_subsection("⚠️ The commit message does not follow the conventions");
+        for problem in &problems {
+            println!("  • {}", problem);
+        }
+        println!();
+        if !ui::confirm("Edit it again?", true)? {
+            return Ok(None);
+        }
+    }
+}
+
+/// Mentions which provider answered when a fallback chain is configured
+fn report_provider(config: &Config) {
+    let answered_by = config.model.answered_by();
+    if answered_by != config.model.name() {
+        println!("🤖 Answered by {}\n", answered_by);
+    }
+}
+
 fn display_contributor_info(contributor: &git::ContributorStats) {
     ui::print_section(&format!("👤 Contributor Details: {}", contributor.name));
     println!("📧 Email: {}", contributor.email);
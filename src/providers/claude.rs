@@ -1,43 +1,40 @@
 use async_trait::async_trait;
 use std::error::Error;
-use reqwest::Client;
+use futures::TryStreamExt;
+use reqwest::RequestBuilder;
 use serde_json::{json, Value};
 
-use super::Provider;
+use super::response::{self, VendorError};
+use super::{sse, HttpClient, Provider, ProviderError, TextStream};
 
 #[derive(Debug)]
 pub struct ClaudeProvider {
-    client: Client,
+    client: HttpClient,
     api_key: String,
 }
 
 impl ClaudeProvider {
-    pub fn new() -> Self {
+    pub fn new(client: HttpClient) -> Self {
         Self {
-            client: Client::new(),
+            client,
             api_key: std::env::var("ANTHROPIC_API_KEY").expect("ANTHROPIC_API_KEY must be set"),
         }
     }
-}
-
-#[async_trait]
-impl Provider for ClaudeProvider {
-    fn name(&self) -> &str {
-        "Claude"
-    }
 
-    async fn generate_text(
+    fn request(
         &self,
+        model: &str,
         system_prompt: &str,
         user_input: &str,
         temperature: f32,
-    ) -> Result<String, Box<dyn Error>> {
-        let response = self.client
+        stream: bool,
+    ) -> RequestBuilder {
+        self.client
             .post("https://api.anthropic.com/v1/messages")
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", "2023-06-01")
             .json(&json!({
-                "model": "claude-3-5-haiku-latest",
+                "model": model,
                 "messages": [
                     {
                         "role": "user",
@@ -46,16 +43,103 @@ impl Provider for ClaudeProvider {
                 ],
                 "temperature": temperature,
                 "system": system_prompt,
-                "max_tokens": 8192
+                "max_tokens": max_output_tokens(model),
+                "stream": stream
             }))
-            .send()
-            .await?
-            .json::<Value>()
-            .await?;
+    }
+}
+
+#[async_trait]
+impl Provider for ClaudeProvider {
+    fn name(&self) -> &str {
+        "Claude"
+    }
+
+    fn default_model(&self) -> &str {
+        "claude-3-5-haiku-latest"
+    }
+
+    fn models(&self) -> Vec<String> {
+        ["claude-3-5-haiku-latest", "claude-3-5-sonnet-latest", "claude-3-7-sonnet-latest", "claude-3-opus-latest"]
+            .map(str::to_string)
+            .to_vec()
+    }
+
+    fn context_window(&self, _model: &str) -> usize {
+        200_000
+    }
+
+    async fn generate_text(
+        &self,
+        model: &str,
+        system_prompt: &str,
+        user_input: &str,
+        temperature: f32,
+    ) -> Result<String, Box<dyn Error>> {
+        let request = self.request(model, system_prompt, user_input, temperature, false);
+        let response = self.client.send(self.name(), request, describe_error).await?;
+        let body = response::json_body(self.name(), response).await?;
 
-        Ok(response["content"][0]["text"]
+        if body["stop_reason"] == "refusal" {
+            return Err(refused(self.name()).into());
+        }
+        Ok(body["content"][0]["text"]
             .as_str()
-            .unwrap_or("Failed to generate text")
+            .ok_or_else(|| response::missing_text(self.name(), &body))?
             .to_string())
     }
-} 
\ No newline at end of file
+
+    async fn generate_text_stream(
+        &self,
+        model: &str,
+        system_prompt: &str,
+        user_input: &str,
+        temperature: f32,
+    ) -> Result<TextStream, Box<dyn Error>> {
+        let request = self.request(model, system_prompt, user_input, temperature, true);
+        let response = self.client.send_streaming(self.name(), request, describe_error).await?;
+
+        let provider = self.name().to_string();
+        Ok(Box::pin(sse::data_lines(response).try_filter_map(move |data| {
+            let provider = provider.clone();
+            async move {
+                let event: Value = serde_json::from_str(&data)?;
+                match event["type"].as_str() {
+                    Some("content_block_delta") => Ok(event["delta"]["text"].as_str().map(str::to_string)),
+                    Some("message_delta") if event["delta"]["stop_reason"] == "refusal" => {
+                        Err(refused(&provider).into())
+                    }
+                    Some("error") => {
+                        let error = describe_error(&event);
+                        Err(response::classify(&provider, reqwest::StatusCode::OK, None, error).into())
+                    }
+                    _ => Ok(None),
+                }
+            }
+        })))
+    }
+}
+
+/// Claude 3 models stop at 4096 output tokens and reject a larger `max_tokens`
+fn max_output_tokens(model: &str) -> u32 {
+    if ["claude-3-opus", "claude-3-sonnet", "claude-3-haiku"].iter().any(|prefix| model.starts_with(prefix)) {
+        4096
+    } else {
+        8192
+    }
+}
+
+/// Anthropic errors look like `{"type": "error", "error": {"type": ..., "message": ...}}`
+fn describe_error(body: &Value) -> VendorError {
+    VendorError {
+        code: body["error"]["type"].as_str().map(str::to_string),
+        message: body["error"]["message"].as_str().unwrap_or_default().to_string(),
+    }
+}
+
+fn refused(provider: &str) -> ProviderError {
+    ProviderError::SafetyBlocked {
+        provider: provider.to_string(),
+        reason: "the model declined to respond".to_string(),
+    }
+}
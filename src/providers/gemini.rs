@@ -1,39 +1,35 @@
 use async_trait::async_trait;
 use std::error::Error;
-use reqwest::Client;
+use futures::TryStreamExt;
+use reqwest::RequestBuilder;
 use serde_json::{json, Value};
 
-use super::Provider;
+use super::response::{self, VendorError};
+use super::{sse, HttpClient, Provider, ProviderError, TextStream};
 
 #[derive(Debug)]
 pub struct GeminiProvider {
-    client: Client,
+    client: HttpClient,
     api_key: String,
 }
 
 impl GeminiProvider {
-    pub fn new() -> Self {
+    pub fn new(client: HttpClient) -> Self {
         Self {
-            client: Client::new(),
+            client,
             api_key: std::env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY must be set"),
         }
     }
-}
-
-#[async_trait]
-impl Provider for GeminiProvider {
-    fn name(&self) -> &str {
-        "Gemini"
-    }
 
-    async fn generate_text(
+    fn request(
         &self,
+        url: String,
         system_prompt: &str,
         user_input: &str,
         temperature: f32,
-    ) -> Result<String, Box<dyn Error>> {
-        let response = self.client
-            .post("https://generativelanguage.googleapis.com/v1/models/gemini-2.0-flash:generateContent")
+    ) -> RequestBuilder {
+        self.client
+            .post(url)
             .query(&[("key", &self.api_key)])
             .json(&json!({
                 "contents": [{
@@ -46,14 +42,98 @@ impl Provider for GeminiProvider {
                     "temperature": temperature
                 }
             }))
-            .send()
-            .await?
-            .json::<Value>()
-            .await?;
+    }
+}
+
+#[async_trait]
+impl Provider for GeminiProvider {
+    fn name(&self) -> &str {
+        "Gemini"
+    }
+
+    fn default_model(&self) -> &str {
+        "gemini-2.0-flash"
+    }
+
+    fn models(&self) -> Vec<String> {
+        ["gemini-2.0-flash", "gemini-2.0-flash-lite", "gemini-1.5-pro", "gemini-1.5-flash"]
+            .map(str::to_string)
+            .to_vec()
+    }
+
+    fn context_window(&self, model: &str) -> usize {
+        if model.starts_with("gemini-1.5-pro") { 2_097_152 } else { 1_048_576 }
+    }
+
+    async fn generate_text(
+        &self,
+        model: &str,
+        system_prompt: &str,
+        user_input: &str,
+        temperature: f32,
+    ) -> Result<String, Box<dyn Error>> {
+        let url = format!("https://generativelanguage.googleapis.com/v1/models/{}:generateContent", model);
+        let request = self.request(url, system_prompt, user_input, temperature);
+        let response = self.client.send(self.name(), request, describe_error).await?;
+        let body = response::json_body(self.name(), response).await?;
+
+        match extract_text(self.name(), &body)? {
+            Some(text) => Ok(text),
+            None => Err(response::missing_text(self.name(), &body).into()),
+        }
+    }
+
+    async fn generate_text_stream(
+        &self,
+        model: &str,
+        system_prompt: &str,
+        user_input: &str,
+        temperature: f32,
+    ) -> Result<TextStream, Box<dyn Error>> {
+        let url = format!("https://generativelanguage.googleapis.com/v1/models/{}:streamGenerateContent", model);
+        let request = self.request(url, system_prompt, user_input, temperature)
+            .query(&[("alt", "sse")]);
+        let response = self.client.send_streaming(self.name(), request, describe_error).await?;
 
-        Ok(response["candidates"][0]["content"]["parts"][0]["text"]
-            .as_str()
-            .unwrap_or("Failed to generate text")
-            .to_string())
+        let provider = self.name().to_string();
+        Ok(Box::pin(sse::data_lines(response).try_filter_map(move |data| {
+            let provider = provider.clone();
+            async move {
+                let event: Value = serde_json::from_str(&data)?;
+                Ok(extract_text(&provider, &event)?)
+            }
+        })))
     }
-} 
\ No newline at end of file
+}
+
+/// Text of the first candidate, or a safety error when the prompt or candidate was blocked
+fn extract_text(provider: &str, body: &Value) -> Result<Option<String>, ProviderError> {
+    if let Some(reason) = body["promptFeedback"]["blockReason"].as_str() {
+        return Err(blocked(provider, reason));
+    }
+    let candidate = &body["candidates"][0];
+    let text = candidate["content"]["parts"][0]["text"].as_str();
+    match candidate["finishReason"].as_str() {
+        Some(reason @ ("SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII")) if text.is_none() => {
+            Err(blocked(provider, reason))
+        }
+        _ => Ok(text.map(str::to_string)),
+    }
+}
+
+/// Google errors look like `{"error": {"code": 400, "status": ..., "message": ...}}`,
+/// wrapped in an array by the streaming endpoint
+fn describe_error(body: &Value) -> VendorError {
+    let error = if body.is_array() { &body[0]["error"] } else { &body["error"] };
+    VendorError {
+        code: error["status"].as_str().map(str::to_string),
+        message: error["message"].as_str().unwrap_or_default().to_string(),
+    }
+}
+
+fn blocked(provider: &str, reason: &str) -> ProviderError {
+    ProviderError::SafetyBlocked {
+        provider: provider.to_string(),
+        reason: format!("blocked with reason {}", reason),
+    }
+}
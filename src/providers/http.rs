@@ -0,0 +1,176 @@
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use reqwest::{Client, RequestBuilder, Response};
+use serde_json::Value;
+
+use super::response::{self, VendorError};
+use super::ProviderError;
+
+/// Timeouts and retry behaviour shared by all providers
+#[derive(Debug, Clone)]
+pub struct HttpSettings {
+    /// Limit for a whole request, or until the first byte for streamed responses
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+    /// Retries after the first attempt on rate limits, server errors and dropped connections
+    pub max_retries: u32,
+    /// Backoff before the first retry; doubled for every further attempt
+    pub initial_backoff: Duration,
+    /// Upper bound for a single backoff wait. A `Retry-After` asking for longer than this is not
+    /// waited for; the request fails with the rate limit error instead
+    pub max_backoff: Duration,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(120),
+            connect_timeout: Duration::from_secs(10),
+            max_retries: 3,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+impl HttpSettings {
+    /// Defaults overridden by `MERIT_HTTP_TIMEOUT_SECS` and `MERIT_HTTP_MAX_RETRIES`
+    pub fn from_env() -> Self {
+        Self::default().with_env_overrides()
+    }
+
+    /// Apply `MERIT_HTTP_TIMEOUT_SECS` and `MERIT_HTTP_MAX_RETRIES` on top of these settings
+    pub fn with_env_overrides(self) -> Self {
+        let mut settings = self;
+        if let Some(secs) = std::env::var("MERIT_HTTP_TIMEOUT_SECS").ok().and_then(|v| v.parse().ok()) {
+            settings.timeout = Duration::from_secs(secs);
+        }
+        if let Some(retries) = std::env::var("MERIT_HTTP_MAX_RETRIES").ok().and_then(|v| v.parse().ok()) {
+            settings.max_retries = retries;
+        }
+        settings
+    }
+}
+
+/// Details of a retry that is about to happen
+#[derive(Debug)]
+pub struct RetryNotice<'a> {
+    pub provider: &'a str,
+    /// 1 for the first retry
+    pub attempt: u32,
+    pub max_retries: u32,
+    pub wait: Duration,
+    pub error: &'a ProviderError,
+}
+
+/// Callback informed about every retry, e.g. to update a spinner
+pub type RetryObserver = Box<dyn Fn(&RetryNotice) + Send + Sync>;
+
+static RETRY_OBSERVER: Mutex<Option<RetryObserver>> = Mutex::new(None);
+
+/// Replace the process-wide retry observer
+pub fn set_retry_observer(observer: Option<RetryObserver>) {
+    *RETRY_OBSERVER.lock().unwrap_or_else(|e| e.into_inner()) = observer;
+}
+
+fn notify_retry(notice: &RetryNotice) {
+    if let Some(observer) = RETRY_OBSERVER.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        observer(notice);
+    }
+}
+
+/// HTTP client with timeouts and jittered exponential backoff
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    client: Client,
+    settings: HttpSettings,
+}
+
+impl HttpClient {
+    pub fn new(settings: HttpSettings) -> Self {
+        let client = Client::builder()
+            .connect_timeout(settings.connect_timeout)
+            .build()
+            .expect("Failed to build HTTP client");
+        Self { client, settings }
+    }
+
+    pub fn post(&self, url: impl reqwest::IntoUrl) -> RequestBuilder {
+        self.client.post(url)
+    }
+
+    /// Send a request whose body is read in full, retrying where that can help
+    pub async fn send(
+        &self,
+        provider: &str,
+        request: RequestBuilder,
+        describe: fn(&Value) -> VendorError,
+    ) -> Result<Response, ProviderError> {
+        self.send_with_retries(provider, request.timeout(self.settings.timeout), describe).await
+    }
+
+    /// Send a request for a streamed body; the timeout only covers waiting for the response to start
+    pub async fn send_streaming(
+        &self,
+        provider: &str,
+        request: RequestBuilder,
+        describe: fn(&Value) -> VendorError,
+    ) -> Result<Response, ProviderError> {
+        self.send_with_retries(provider, request, describe).await
+    }
+
+    async fn send_with_retries(
+        &self,
+        provider: &str,
+        request: RequestBuilder,
+        describe: fn(&Value) -> VendorError,
+    ) -> Result<Response, ProviderError> {
+        let mut attempt = 0;
+        loop {
+            // JSON bodies can always be cloned, so this only fails for streamed uploads
+            let this_request = request.try_clone().expect("Request body must be cloneable");
+            let error = match tokio::time::timeout(self.settings.timeout, this_request.send()).await {
+                Err(_) => ProviderError::Timeout { provider: provider.to_string() },
+                Ok(Err(e)) if e.is_timeout() => ProviderError::Timeout { provider: provider.to_string() },
+                Ok(Err(e)) => ProviderError::Network { provider: provider.to_string(), message: e.to_string() },
+                Ok(Ok(response)) => match response::check_status(provider, response, describe).await {
+                    Ok(response) => return Ok(response),
+                    Err(e) => e,
+                },
+            };
+
+            if !error.is_retryable() || attempt >= self.settings.max_retries {
+                return Err(error);
+            }
+            let wait = match &error {
+                ProviderError::RateLimited { retry_after: Some(wait), .. } => {
+                    // Retrying earlier than asked would only be rejected again
+                    if *wait > self.settings.max_backoff {
+                        return Err(error);
+                    }
+                    *wait
+                }
+                _ => self.backoff(attempt),
+            };
+
+            attempt += 1;
+            notify_retry(&RetryNotice {
+                provider,
+                attempt,
+                max_retries: self.settings.max_retries,
+                wait,
+                error: &error,
+            });
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Exponential backoff with jitter between 50% and 100% of the nominal delay
+    fn backoff(&self, attempt: u32) -> Duration {
+        let nominal = self.settings.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.settings.max_backoff);
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or(0);
+        nominal.mul_f64(0.5 + (nanos % 1000) as f64 / 2000.0)
+    }
+}
@@ -0,0 +1,103 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+
+use super::Provider;
+
+/// Stable FNV-1a hash of a prompt, used to key canned responses and replay fixtures
+pub fn prompt_hash(system_prompt: &str, user_input: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in system_prompt.bytes().chain([0]).chain(user_input.bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// A prompt the mock provider was asked to answer
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub model: String,
+    pub system_prompt: String,
+    pub user_input: String,
+}
+
+/// Provider returning canned responses keyed by prompt hash, for tests without network access
+#[derive(Debug, Default)]
+pub struct MockProvider {
+    responses: HashMap<String, String>,
+    default_response: Option<String>,
+    context_window: Option<usize>,
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+}
+
+impl MockProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer `response` when asked exactly this prompt
+    pub fn with_response(mut self, system_prompt: &str, user_input: &str, response: &str) -> Self {
+        self.responses.insert(prompt_hash(system_prompt, user_input), response.to_string());
+        self
+    }
+
+    /// Answer `response` to every prompt without a canned response of its own
+    pub fn with_default_response(mut self, response: &str) -> Self {
+        self.default_response = Some(response.to_string());
+        self
+    }
+
+    /// Pretend the model accepts only `tokens` tokens per request
+    pub fn with_context_window(mut self, tokens: usize) -> Self {
+        self.context_window = Some(tokens);
+        self
+    }
+
+    /// Every request received so far, oldest first
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Handle on the request log that stays usable after the provider is moved into an analyzer
+    pub fn request_log(&self) -> Arc<Mutex<Vec<MockRequest>>> {
+        Arc::clone(&self.requests)
+    }
+}
+
+#[async_trait]
+impl Provider for MockProvider {
+    fn name(&self) -> &str {
+        "Mock"
+    }
+
+    fn default_model(&self) -> &str {
+        "mock"
+    }
+
+    fn context_window(&self, _model: &str) -> usize {
+        self.context_window.unwrap_or(super::DEFAULT_CONTEXT_WINDOW)
+    }
+
+    async fn generate_text(
+        &self,
+        model: &str,
+        system_prompt: &str,
+        user_input: &str,
+        _temperature: f32,
+    ) -> Result<String, Box<dyn Error>> {
+        self.requests.lock().unwrap_or_else(|e| e.into_inner()).push(MockRequest {
+            model: model.to_string(),
+            system_prompt: system_prompt.to_string(),
+            user_input: user_input.to_string(),
+        });
+
+        let hash = prompt_hash(system_prompt, user_input);
+        self.responses
+            .get(&hash)
+            .or(self.default_response.as_ref())
+            .cloned()
+            .ok_or_else(|| format!("MockProvider has no response for prompt {}", hash).into())
+    }
+}
@@ -1,37 +1,137 @@
 use async_trait::async_trait;
 use std::error::Error;
 use std::fmt::Debug;
+use std::pin::Pin;
+use std::time::Duration;
 use dialoguer::{theme::ColorfulTheme, Select};
+use futures::Stream;
 
-pub mod openai;
+pub mod openai_compatible;
 pub mod claude;
 pub mod gemini;
-pub mod deepseek;
+pub mod http;
+pub mod mock;
+pub mod replay;
+pub mod response;
+pub mod sse;
 
-pub use openai::OpenAIProvider;
+pub use openai_compatible::{OpenAICompatibleConfig, OpenAICompatibleProvider};
 pub use claude::ClaudeProvider;
 pub use gemini::GeminiProvider;
-pub use deepseek::DeepSeekProvider;
+pub use http::{HttpClient, HttpSettings};
+pub use mock::MockProvider;
+pub use replay::ReplayProvider;
+
+/// Context window assumed for models without a known limit
+pub const DEFAULT_CONTEXT_WINDOW: usize = 8_192;
+
+/// Requests sent to a provider at once, to stay clear of rate limits
+pub const MAX_CONCURRENT_REQUESTS: usize = 4;
+
+/// Token estimate of about three characters per token, which errs on the safe side for code and diffs
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(3)
+}
 
 /// Base trait for AI model providers with general capabilities
 #[async_trait]
 pub trait Provider: Send + Sync + Debug {
     fn name(&self) -> &str;
+
+    /// Model used when none is requested explicitly
+    fn default_model(&self) -> &str;
+
+    /// Models offered in the model picker, default first
+    fn models(&self) -> Vec<String> {
+        vec![self.default_model().to_string()]
+    }
+
+    /// Tokens `model` accepts in a single request, prompt and answer together
+    fn context_window(&self, _model: &str) -> usize {
+        DEFAULT_CONTEXT_WINDOW
+    }
+
+    /// Rough number of tokens `text` takes up with `model`
+    fn estimate_tokens(&self, _model: &str, text: &str) -> usize {
+        estimate_tokens(text)
+    }
     
-    /// Generate text based on a system prompt and user input
+    /// Generate text with the given model based on a system prompt and user input
     async fn generate_text(
         &self,
+        model: &str,
         system_prompt: &str,
         user_input: &str,
         temperature: f32,
     ) -> Result<String, Box<dyn Error>>;
+
+    /// Generate text like `generate_text`, yielding chunks as they arrive.
+    /// Providers without streaming support yield the whole response as one chunk.
+    async fn generate_text_stream(
+        &self,
+        model: &str,
+        system_prompt: &str,
+        user_input: &str,
+        temperature: f32,
+    ) -> Result<TextStream, Box<dyn Error>> {
+        let text = self.generate_text(model, system_prompt, user_input, temperature).await?;
+        Ok(Box::pin(futures::stream::once(async move { Ok(text) })))
+    }
 }
 
-/// Error type for provider selection
+/// Error yielded by a `TextStream`; `Send` so streams can cross await points
+pub type StreamError = Box<dyn Error + Send + Sync>;
+
+/// Chunks of generated text in the order they were produced
+pub type TextStream = Pin<Box<dyn Stream<Item = Result<String, StreamError>> + Send>>;
+
+/// Error type for provider selection and requests
 #[derive(Debug)]
 pub enum ProviderError {
     NoProvidersAvailable,
     InvalidSelection,
+    UnknownProvider(String),
+    /// The API key was missing, invalid or lacks permission
+    Auth { provider: String, message: String },
+    /// Too many requests; `retry_after` is the server's suggested wait
+    RateLimited { provider: String, retry_after: Option<Duration>, message: String },
+    /// The account is out of credits or over its usage quota
+    QuotaExceeded { provider: String, message: String },
+    /// The prompt or the response was blocked by a content filter
+    SafetyBlocked { provider: String, reason: String },
+    /// Any other unsuccessful HTTP status
+    Http { provider: String, status: u16, message: String },
+    /// The response could not be understood
+    MalformedResponse { provider: String, message: String },
+    /// No response arrived within the configured timeout
+    Timeout { provider: String },
+    /// The connection could not be established or was dropped
+    Network { provider: String, message: String },
+}
+
+impl ProviderError {
+    /// Whether sending the same request again may succeed
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::RateLimited { .. } | Self::Timeout { .. } | Self::Network { .. } => true,
+            Self::Http { status, .. } => *status == 408 || *status >= 500,
+            _ => false,
+        }
+    }
+
+    /// Suggestion shown to the user alongside the error
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            Self::Auth { .. } => Some("Check the API key configured for this provider."),
+            Self::RateLimited { .. } => Some("Wait a moment and try again, or pick another provider."),
+            Self::QuotaExceeded { .. } => Some("Check the billing and usage limits of your account."),
+            Self::SafetyBlocked { .. } => Some("The content was refused by the provider's safety filter."),
+            Self::Http { status, .. } if *status >= 500 => Some("The provider is having problems; try again later."),
+            Self::Timeout { .. } => Some("Raise MERIT_HTTP_TIMEOUT_SECS if the provider is just slow."),
+            Self::Network { .. } => Some("Check your network connection and the provider's address."),
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for ProviderError {
@@ -39,32 +139,72 @@ impl std::fmt::Display for ProviderError {
         match self {
             Self::NoProvidersAvailable => write!(f, "No AI providers available"),
             Self::InvalidSelection => write!(f, "Invalid provider selection"),
+            Self::UnknownProvider(name) => write!(f, "Provider '{}' is not available", name),
+            Self::Auth { provider, message } => write!(f, "{} rejected the credentials: {}", provider, message),
+            Self::RateLimited { provider, retry_after: Some(wait), message } => {
+                write!(f, "{} rate limit reached, retry in {}s: {}", provider, wait.as_secs().max(1), message)
+            }
+            Self::RateLimited { provider, retry_after: None, message } => {
+                write!(f, "{} rate limit reached: {}", provider, message)
+            }
+            Self::QuotaExceeded { provider, message } => write!(f, "{} quota exceeded: {}", provider, message),
+            Self::SafetyBlocked { provider, reason } => write!(f, "{} blocked the request: {}", provider, reason),
+            Self::Http { provider, status, message } => write!(f, "{} returned HTTP {}: {}", provider, status, message),
+            Self::MalformedResponse { provider, message } => {
+                write!(f, "{} sent a response that could not be read: {}", provider, message)
+            }
+            Self::Timeout { provider } => write!(f, "{} did not respond in time", provider),
+            Self::Network { provider, message } => write!(f, "Could not reach {}: {}", provider, message),
         }
     }
 }
 
 impl Error for ProviderError {}
 
-/// Get all available providers based on environment variables
-pub fn get_available_providers() -> Vec<Box<dyn Provider>> {
+/// Get all available providers based on environment variables and the configured
+/// OpenAI-compatible endpoints.
+/// `MERIT_REPLAY_MODE=record|replay` with `MERIT_REPLAY_DIR` records every exchange
+/// as a fixture, or answers from recorded fixtures without any API key.
+pub fn get_available_providers(http: &HttpSettings, custom: &[OpenAICompatibleConfig]) -> Vec<Box<dyn Provider>> {
     use std::env;
 
+    let replay_dir = env::var("MERIT_REPLAY_DIR").unwrap_or_else(|_| "fixtures".to_string());
+    let replay_mode = env::var("MERIT_REPLAY_MODE").unwrap_or_default();
+    if replay_mode == "replay" {
+        return vec![Box::new(ReplayProvider::replay(replay_dir))];
+    }
+
+    let http = HttpClient::new(http.clone());
     let mut providers = Vec::new();
     
     if env::var("OPENAI_API_KEY").is_ok() {
-        providers.push(Box::new(OpenAIProvider::new()) as Box<dyn Provider>);
+        providers.push(Box::new(OpenAICompatibleProvider::openai(http.clone())) as Box<dyn Provider>);
     }
     
     if env::var("ANTHROPIC_API_KEY").is_ok() {
-        providers.push(Box::new(ClaudeProvider::new()) as Box<dyn Provider>);
+        providers.push(Box::new(ClaudeProvider::new(http.clone())) as Box<dyn Provider>);
     }
     
     if env::var("DEEPSEEK_API_KEY").is_ok() {
-        providers.push(Box::new(DeepSeekProvider::new()) as Box<dyn Provider>);
+        providers.push(Box::new(OpenAICompatibleProvider::deepseek(http.clone())) as Box<dyn Provider>);
     }
     
     if env::var("GEMINI_API_KEY").is_ok() {
-        providers.push(Box::new(GeminiProvider::new()) as Box<dyn Provider>);
+        providers.push(Box::new(GeminiProvider::new(http.clone())) as Box<dyn Provider>);
+    }
+
+    if env::var("OLLAMA_HOST").is_ok() {
+        providers.push(Box::new(OpenAICompatibleProvider::ollama(http.clone())) as Box<dyn Provider>);
+    }
+
+    // A key variable that is named but unset, e.g. through a typo, skips the provider
+    for config in OpenAICompatibleConfig::from_env().into_iter().chain(custom.iter().cloned()) {
+        match &config.api_key_env {
+            Some(var) if env::var(var).is_err() => {
+                eprintln!("Skipping provider '{}': {} is not set", config.name, var);
+            }
+            _ => providers.push(Box::new(OpenAICompatibleProvider::new(config, http.clone())) as Box<dyn Provider>),
+        }
     }
 
     if providers.is_empty() {
@@ -73,8 +213,17 @@ pub fn get_available_providers() -> Vec<Box<dyn Provider>> {
         eprintln!("  ANTHROPIC_API_KEY for Claude");
         eprintln!("  DEEPSEEK_API_KEY for DeepSeek");
         eprintln!("  GEMINI_API_KEY for Google");
+        eprintln!("  OLLAMA_HOST for a local Ollama or llama.cpp server");
+        eprintln!("  OPENAI_COMPATIBLE_BASE_URL for any OpenAI-compatible endpoint");
         std::process::exit(1);
     }
+
+    if replay_mode == "record" {
+        providers = providers
+            .into_iter()
+            .map(|p| Box::new(ReplayProvider::record(p, &replay_dir)) as Box<dyn Provider>)
+            .collect();
+    }
     
     providers
 }
@@ -95,4 +244,12 @@ pub fn select_provider(providers: &[Box<dyn Provider>]) -> Result<usize, Box<dyn
         .items(&provider_names)
         .default(0)
         .interact()?)
-} 
\ No newline at end of file
+} 
+
+/// Find a provider by its name, ignoring case
+pub fn find_provider(providers: &[Box<dyn Provider>], name: &str) -> Result<usize, Box<dyn Error>> {
+    providers
+        .iter()
+        .position(|p| p.name().eq_ignore_ascii_case(name))
+        .ok_or_else(|| Box::new(ProviderError::UnknownProvider(name.to_string())) as Box<dyn Error>)
+}
\ No newline at end of file
@@ -0,0 +1,296 @@
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::error::Error;
+use futures::TryStreamExt;
+use reqwest::RequestBuilder;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::response::{self, VendorError};
+use super::{sse, HttpClient, Provider, ProviderError, TextStream};
+
+/// Connection settings for an endpoint speaking the OpenAI chat completions protocol
+#[derive(Debug, Clone, Deserialize)]
+pub struct OpenAICompatibleConfig {
+    /// Name shown in the provider menu and accepted by `--provider`
+    pub name: String,
+    /// URL that `/chat/completions` is appended to; may carry a query string (e.g. Azure's `api-version`)
+    pub base_url: String,
+    /// Model requested when none is given explicitly
+    pub model: String,
+    /// Further models offered in the model picker
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// Environment variable holding the API key, if the endpoint needs one
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+    /// Header carrying the API key; `Authorization` sends it as a bearer token
+    #[serde(default = "default_auth_header")]
+    pub auth_header: String,
+    /// Extra headers sent with every request
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Tokens the models accept per request; a conservative default when unknown
+    #[serde(default)]
+    pub context_window: Option<usize>,
+}
+
+impl OpenAICompatibleConfig {
+    pub fn new(name: &str, base_url: &str, model: &str, api_key_env: Option<&str>) -> Self {
+        Self {
+            name: name.to_string(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            models: Vec::new(),
+            api_key_env: api_key_env.map(str::to_string),
+            auth_header: default_auth_header(),
+            headers: BTreeMap::new(),
+            context_window: None,
+        }
+    }
+
+    /// Read a custom endpoint from the `OPENAI_COMPATIBLE_*` environment variables
+    pub fn from_env() -> Option<Self> {
+        use std::env;
+
+        let base_url = env::var("OPENAI_COMPATIBLE_BASE_URL").ok()?;
+        let name = env::var("OPENAI_COMPATIBLE_NAME").unwrap_or_else(|_| "OpenAI-compatible".to_string());
+        let model = env::var("OPENAI_COMPATIBLE_MODEL").unwrap_or_else(|_| "default".to_string());
+        let api_key_env = env::var("OPENAI_COMPATIBLE_API_KEY_ENV").ok()
+            .or_else(|| env::var("OPENAI_COMPATIBLE_API_KEY").ok().map(|_| "OPENAI_COMPATIBLE_API_KEY".to_string()));
+
+        let mut config = Self::new(&name, &base_url, &model, api_key_env.as_deref());
+        if let Ok(header) = env::var("OPENAI_COMPATIBLE_AUTH_HEADER") {
+            config.auth_header = header;
+        }
+        if let Ok(headers) = env::var("OPENAI_COMPATIBLE_HEADERS") {
+            config.headers = parse_headers(&headers);
+        }
+        if let Ok(models) = env::var("OPENAI_COMPATIBLE_MODELS") {
+            config.models = models.split(',').map(|m| m.trim().to_string()).filter(|m| !m.is_empty()).collect();
+        }
+        config.context_window = env::var("OPENAI_COMPATIBLE_CONTEXT_WINDOW").ok().and_then(|v| v.parse().ok());
+        Some(config)
+    }
+}
+
+fn default_auth_header() -> String {
+    "Authorization".to_string()
+}
+
+/// Parse `Name: value; Other-Name: value` into header pairs
+fn parse_headers(headers: &str) -> BTreeMap<String, String> {
+    headers
+        .split(';')
+        .filter_map(|header| header.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .filter(|(name, _)| !name.is_empty())
+        .collect()
+}
+
+#[derive(Debug)]
+pub struct OpenAICompatibleProvider {
+    client: HttpClient,
+    config: OpenAICompatibleConfig,
+    api_key: Option<String>,
+}
+
+impl OpenAICompatibleProvider {
+    pub fn new(config: OpenAICompatibleConfig, client: HttpClient) -> Self {
+        let api_key = config.api_key_env.as_ref().map(|var| {
+            std::env::var(var).unwrap_or_else(|_| panic!("{} must be set", var))
+        });
+
+        Self {
+            client,
+            config,
+            api_key,
+        }
+    }
+
+    pub fn openai(client: HttpClient) -> Self {
+        let mut config = OpenAICompatibleConfig::new(
+            "OpenAI",
+            "https://api.openai.com/v1",
+            "gpt-4-turbo-preview",
+            Some("OPENAI_API_KEY"),
+        );
+        config.models = ["gpt-4o-mini", "gpt-4o", "gpt-4.1", "o3-mini"].map(str::to_string).to_vec();
+        config.context_window = Some(128_000);
+        Self::new(config, client)
+    }
+
+    pub fn deepseek(client: HttpClient) -> Self {
+        let mut config = OpenAICompatibleConfig::new(
+            "DeepSeek",
+            "https://api.deepseek.com/v1",
+            "deepseek-chat",
+            Some("DEEPSEEK_API_KEY"),
+        );
+        config.models = vec!["deepseek-reasoner".to_string()];
+        config.context_window = Some(64_000);
+        Self::new(config, client)
+    }
+
+    /// Local Ollama or llama.cpp server at `OLLAMA_HOST`
+    pub fn ollama(client: HttpClient) -> Self {
+        let host = std::env::var("OLLAMA_HOST").expect("OLLAMA_HOST must be set");
+        // Ollama itself accepts a bare `host:port`, so do the same here
+        let host = if host.contains("://") { host } else { format!("http://{}", host) };
+        let model = std::env::var("OLLAMA_MODEL").unwrap_or_else(|_| "llama3.2".to_string());
+
+        let mut config = OpenAICompatibleConfig::new(
+            "Ollama",
+            &format!("{}/v1", host.trim_end_matches('/')),
+            &model,
+            None,
+        );
+        // Ollama's default context size, unless raised through `num_ctx`
+        config.context_window = Some(4_096);
+        Self::new(config, client)
+    }
+
+    fn request(
+        &self,
+        model: &str,
+        system_prompt: &str,
+        user_input: &str,
+        temperature: f32,
+        stream: bool,
+    ) -> RequestBuilder {
+        let mut request = self.client.post(self.endpoint());
+        if let Some(api_key) = &self.api_key {
+            request = if self.config.auth_header.eq_ignore_ascii_case("Authorization") {
+                request.bearer_auth(api_key)
+            } else {
+                request.header(&self.config.auth_header, api_key)
+            };
+        }
+        for (name, value) in &self.config.headers {
+            request = request.header(name, value);
+        }
+
+        let mut body = json!({
+            "model": model,
+            "messages": [
+                {
+                    "role": "system",
+                    "content": system_prompt
+                },
+                {
+                    "role": "user",
+                    "content": user_input
+                }
+            ],
+            "stream": stream
+        });
+        if !is_reasoning_model(model) {
+            body["temperature"] = json!(temperature);
+        }
+        request.json(&body)
+    }
+
+    fn endpoint(&self) -> String {
+        match self.config.base_url.split_once('?') {
+            Some((base, query)) => format!("{}/chat/completions?{}", base.trim_end_matches('/'), query),
+            None => format!("{}/chat/completions", self.config.base_url.trim_end_matches('/')),
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for OpenAICompatibleProvider {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn default_model(&self) -> &str {
+        &self.config.model
+    }
+
+    fn models(&self) -> Vec<String> {
+        let mut models = vec![self.config.model.clone()];
+        models.extend(self.config.models.iter().filter(|m| **m != self.config.model).cloned());
+        models
+    }
+
+    fn context_window(&self, _model: &str) -> usize {
+        self.config.context_window.unwrap_or(super::DEFAULT_CONTEXT_WINDOW)
+    }
+
+    async fn generate_text(
+        &self,
+        model: &str,
+        system_prompt: &str,
+        user_input: &str,
+        temperature: f32,
+    ) -> Result<String, Box<dyn Error>> {
+        let request = self.request(model, system_prompt, user_input, temperature, false);
+        let response = self.client.send(self.name(), request, describe_error).await?;
+        let body = response::json_body(self.name(), response).await?;
+
+        let choice = &body["choices"][0];
+        if choice["finish_reason"] == "content_filter" {
+            return Err(content_filtered(self.name()).into());
+        }
+        Ok(choice["message"]["content"]
+            .as_str()
+            .ok_or_else(|| response::missing_text(self.name(), &body))?
+            .to_string())
+    }
+
+    async fn generate_text_stream(
+        &self,
+        model: &str,
+        system_prompt: &str,
+        user_input: &str,
+        temperature: f32,
+    ) -> Result<TextStream, Box<dyn Error>> {
+        let request = self.request(model, system_prompt, user_input, temperature, true);
+        let response = self.client.send_streaming(self.name(), request, describe_error).await?;
+
+        let provider = self.name().to_string();
+        Ok(Box::pin(sse::data_lines(response).try_filter_map(move |data| {
+            let provider = provider.clone();
+            async move {
+                if data == "[DONE]" {
+                    return Ok(None);
+                }
+                let event: Value = serde_json::from_str(&data)?;
+                if event["error"].is_object() {
+                    let error = describe_error(&event);
+                    return Err(response::classify(&provider, reqwest::StatusCode::OK, None, error).into());
+                }
+                if event["choices"][0]["finish_reason"] == "content_filter" {
+                    return Err(content_filtered(&provider).into());
+                }
+                Ok(event["choices"][0]["delta"]["content"].as_str().map(str::to_string))
+            }
+        })))
+    }
+}
+
+/// OpenAI's o-series (`o1`, `o3-mini`, `o4-mini`, ...) only accept the default temperature
+fn is_reasoning_model(model: &str) -> bool {
+    let mut chars = model.chars();
+    chars.next() == Some('o') && chars.next().is_some_and(|c| c.is_ascii_digit())
+}
+
+/// OpenAI puts `{message, type, code}` under `error`; Ollama and some gateways use a plain string
+fn describe_error(body: &Value) -> VendorError {
+    let error = &body["error"];
+    if let Some(message) = error.as_str() {
+        return VendorError { code: None, message: message.to_string() };
+    }
+    VendorError {
+        code: error["code"].as_str().or(error["type"].as_str()).map(str::to_string),
+        message: error["message"].as_str().unwrap_or_default().to_string(),
+    }
+}
+
+fn content_filtered(provider: &str) -> ProviderError {
+    ProviderError::SafetyBlocked {
+        provider: provider.to_string(),
+        reason: "the response was stopped by the content filter".to_string(),
+    }
+}
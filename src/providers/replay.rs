@@ -0,0 +1,96 @@
+use async_trait::async_trait;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use serde_json::{json, Value};
+
+use super::mock::prompt_hash;
+use super::Provider;
+
+/// Provider that saves real exchanges to disk and serves them back later.
+/// Each exchange is stored as `<prompt hash>.json` in the fixture directory.
+#[derive(Debug)]
+pub struct ReplayProvider {
+    /// Provider whose answers are recorded; `None` when replaying
+    inner: Option<Box<dyn Provider>>,
+    dir: PathBuf,
+    name: String,
+}
+
+impl ReplayProvider {
+    /// Forward requests to `inner` and save every exchange in `dir`
+    pub fn record(inner: Box<dyn Provider>, dir: impl Into<PathBuf>) -> Self {
+        let name = inner.name().to_string();
+        Self { inner: Some(inner), dir: dir.into(), name }
+    }
+
+    /// Answer from the exchanges previously recorded in `dir`
+    pub fn replay(dir: impl Into<PathBuf>) -> Self {
+        Self { inner: None, dir: dir.into(), name: "Replay".to_string() }
+    }
+
+    fn fixture_path(&self, system_prompt: &str, user_input: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", prompt_hash(system_prompt, user_input)))
+    }
+}
+
+#[async_trait]
+impl Provider for ReplayProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn default_model(&self) -> &str {
+        match &self.inner {
+            Some(inner) => inner.default_model(),
+            None => "replay",
+        }
+    }
+
+    fn models(&self) -> Vec<String> {
+        match &self.inner {
+            Some(inner) => inner.models(),
+            None => vec!["replay".to_string()],
+        }
+    }
+
+    fn context_window(&self, model: &str) -> usize {
+        match &self.inner {
+            Some(inner) => inner.context_window(model),
+            None => super::DEFAULT_CONTEXT_WINDOW,
+        }
+    }
+
+    async fn generate_text(
+        &self,
+        model: &str,
+        system_prompt: &str,
+        user_input: &str,
+        temperature: f32,
+    ) -> Result<String, Box<dyn Error>> {
+        let path = self.fixture_path(system_prompt, user_input);
+
+        let Some(inner) = &self.inner else {
+            let fixture: Value = match fs::read_to_string(&path) {
+                Ok(content) => serde_json::from_str(&content)?,
+                Err(_) => return Err(format!("No recorded response at {}", path.display()).into()),
+            };
+            return fixture["response"]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| format!("Fixture {} has no response", path.display()).into());
+        };
+
+        let response = inner.generate_text(model, system_prompt, user_input, temperature).await?;
+        fs::create_dir_all(&self.dir)?;
+        fs::write(&path, serde_json::to_string_pretty(&json!({
+            "provider": inner.name(),
+            "model": model,
+            "temperature": temperature,
+            "system_prompt": system_prompt,
+            "user_input": user_input,
+            "response": response,
+        }))?)?;
+        Ok(response)
+    }
+}
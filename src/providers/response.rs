@@ -0,0 +1,108 @@
+use std::time::Duration;
+use reqwest::{header::RETRY_AFTER, Response, StatusCode};
+use serde_json::Value;
+
+use super::ProviderError;
+
+/// Error code and message taken from a vendor's error body
+#[derive(Debug, Default)]
+pub struct VendorError {
+    pub code: Option<String>,
+    pub message: String,
+}
+
+/// Pass successful responses through and turn everything else into a `ProviderError`.
+/// `describe` extracts the vendor's error code and message from the JSON body.
+pub async fn check_status(
+    provider: &str,
+    response: Response,
+    describe: fn(&Value) -> VendorError,
+) -> Result<Response, ProviderError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let retry_after = retry_after(&response);
+    let text = response.text().await.unwrap_or_default();
+    let mut error = describe(&serde_json::from_str(&text).unwrap_or(Value::Null));
+    if error.message.is_empty() {
+        error.message = if text.trim().is_empty() {
+            status.canonical_reason().unwrap_or("no details").to_string()
+        } else {
+            text.trim().to_string()
+        };
+    }
+
+    Err(classify(provider, status, retry_after, error))
+}
+
+/// Map an HTTP status and vendor error onto the matching `ProviderError`
+pub fn classify(
+    provider: &str,
+    status: StatusCode,
+    retry_after: Option<Duration>,
+    error: VendorError,
+) -> ProviderError {
+    let provider = provider.to_string();
+    let code = error.code.unwrap_or_default().to_ascii_lowercase();
+    let message = error.message;
+    let lowercase_message = message.to_ascii_lowercase();
+
+    if status == StatusCode::UNAUTHORIZED
+        || status == StatusCode::FORBIDDEN
+        || ["authentication", "permission", "unauthenticated", "invalid_api_key"].iter().any(|c| code.contains(c))
+    {
+        ProviderError::Auth { provider, message }
+    } else if status == StatusCode::PAYMENT_REQUIRED
+        || code == "insufficient_quota"
+        || lowercase_message.contains("credit balance")
+        || lowercase_message.contains("billing")
+    {
+        ProviderError::QuotaExceeded { provider, message }
+    } else if status == StatusCode::TOO_MANY_REQUESTS || code.contains("rate_limit") || code == "resource_exhausted" {
+        ProviderError::RateLimited { provider, retry_after, message }
+    } else if code.contains("content_filter") || code.contains("safety") {
+        ProviderError::SafetyBlocked { provider, reason: message }
+    } else if code.contains("overloaded") {
+        // Anthropic reports overload as 529, also mid-stream where there is no status
+        ProviderError::Http { provider, status: 529, message }
+    } else {
+        ProviderError::Http { provider, status: status.as_u16(), message }
+    }
+}
+
+/// Parse the JSON body of a successful response
+pub async fn json_body(provider: &str, response: Response) -> Result<Value, ProviderError> {
+    response.json::<Value>().await.map_err(|e| {
+        if e.is_timeout() {
+            ProviderError::Timeout { provider: provider.to_string() }
+        } else {
+            ProviderError::MalformedResponse { provider: provider.to_string(), message: e.to_string() }
+        }
+    })
+}
+
+/// Error for a successful response that carries no generated text
+pub fn missing_text(provider: &str, body: &Value) -> ProviderError {
+    let mut body = body.to_string();
+    if body.len() > 200 {
+        let cut = (0..=200).rev().find(|&i| body.is_char_boundary(i)).unwrap_or(0);
+        body.truncate(cut);
+        body.push('…');
+    }
+    ProviderError::MalformedResponse {
+        provider: provider.to_string(),
+        message: format!("no generated text in {}", body),
+    }
+}
+
+/// `Retry-After` as delta-seconds or as an HTTP date; a date in the past means no wait
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<f64>() {
+        return (secs >= 0.0).then(|| Duration::from_secs_f64(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some((date.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().unwrap_or_default())
+}
@@ -0,0 +1,33 @@
+use futures::{stream, Stream, StreamExt};
+use reqwest::Response;
+
+use super::StreamError;
+
+/// Split a server-sent events response into the payloads of its `data:` lines
+pub fn data_lines(response: Response) -> impl Stream<Item = Result<String, StreamError>> + Send {
+    stream::try_unfold((response.bytes_stream(), Vec::new(), false), |(mut bytes, mut buffer, mut done)| async move {
+        loop {
+            let line_end = buffer.iter().position(|&b| b == b'\n');
+            if line_end.is_none() && !done {
+                match bytes.next().await {
+                    Some(chunk) => buffer.extend_from_slice(&chunk?),
+                    // Flush a last line that wasn't newline-terminated
+                    None => done = true,
+                }
+                continue;
+            }
+            if buffer.is_empty() {
+                return Ok(None);
+            }
+
+            let line: Vec<u8> = match line_end {
+                Some(end) => buffer.drain(..=end).collect(),
+                None => std::mem::take(&mut buffer),
+            };
+            let line = String::from_utf8_lossy(&line);
+            if let Some(data) = line.trim_end_matches(['\r', '\n']).strip_prefix("data:") {
+                return Ok(Some((data.trim_start().to_string(), (bytes, buffer, done))));
+            }
+        }
+    })
+}
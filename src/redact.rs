@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::OnceLock;
+use regex::Regex;
+use serde::Deserialize;
+
+/// What happens to secrets found in text bound for a provider, from the loosest to the strictest
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RedactionPolicy {
+    /// Send the text unchanged
+    Off,
+    /// Mask every secret and send the rest
+    #[default]
+    Redact,
+    /// Refuse to send text containing secrets
+    Block,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SecretKind {
+    PrivateKey,
+    ApiKey,
+    Credential,
+    Email,
+    HighEntropy,
+}
+
+impl SecretKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SecretKind::PrivateKey => "private key",
+            SecretKind::ApiKey => "API key",
+            SecretKind::Credential => "credential",
+            SecretKind::Email => "email address",
+            SecretKind::HighEntropy => "high-entropy string",
+        }
+    }
+
+    fn placeholder(&self) -> String {
+        format!("[REDACTED {}]", self.label().to_uppercase())
+    }
+}
+
+/// A secret that was masked, and the file of the diff it appeared in
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub kind: SecretKind,
+    pub file: Option<String>,
+}
+
+/// Text with its secrets masked
+#[derive(Debug, Clone)]
+pub struct Redacted {
+    pub text: String,
+    pub findings: Vec<Finding>,
+}
+
+/// Returned instead of the text when the policy blocks requests containing secrets
+#[derive(Debug)]
+pub struct Blocked {
+    pub findings: Vec<Finding>,
+}
+
+impl fmt::Display for Blocked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Request blocked: the changes contain {} possible secret(s) and the redaction policy is `block`",
+            self.findings.len()
+        )
+    }
+}
+
+impl Error for Blocked {}
+
+struct Detector {
+    kind: SecretKind,
+    /// Masks the `secret` group if present, otherwise the whole match
+    pattern: Regex,
+    /// Extra check for matches that are only suspicious, such as high-entropy strings
+    confirm: Option<fn(&str) -> bool>,
+}
+
+fn detectors() -> &'static [Detector] {
+    static DETECTORS: OnceLock<Vec<Detector>> = OnceLock::new();
+    DETECTORS.get_or_init(|| {
+        let detector = |kind, pattern: &str, confirm| Detector {
+            kind,
+            pattern: Regex::new(pattern).expect("Built-in redaction pattern must compile"),
+            confirm,
+        };
+        vec![
+            detector(
+                SecretKind::PrivateKey,
+                // Body lines may carry a diff marker; a missing END line still masks the body
+                r"-----BEGIN [A-Z ]*PRIVATE KEY-----(?:\n[+ -]?[A-Za-z0-9/=][A-Za-z0-9+/=]*)*(?:\n[+ -]?-----END [A-Z ]*PRIVATE KEY-----)?",
+                None,
+            ),
+            detector(
+                SecretKind::ApiKey,
+                concat!(
+                    r"\b(?:sk-(?:ant-|proj-)?[A-Za-z0-9_-]{20,}",
+                    r"|AKIA[0-9A-Z]{16}",
+                    r"|AIza[0-9A-Za-z_-]{35}",
+                    r"|gh[pousr]_[A-Za-z0-9]{36,}",
+                    r"|github_pat_[A-Za-z0-9_]{40,}",
+                    r"|glpat-[A-Za-z0-9_-]{20,}",
+                    r"|xox[abprs]-[A-Za-z0-9-]{10,})",
+                ),
+                None,
+            ),
+            detector(
+                SecretKind::Credential,
+                r"\beyJ[A-Za-z0-9_-]{8,}\.[A-Za-z0-9_-]{8,}\.[A-Za-z0-9_-]{8,}",
+                None,
+            ),
+            detector(
+                SecretKind::Credential,
+                r"(?i)\bbearer\s+(?P<secret>[A-Za-z0-9._~+/=-]{16,})",
+                None,
+            ),
+            detector(
+                SecretKind::Credential,
+                r#"(?i)[A-Z0-9_.-]*(?:secret|passw(?:or)?d|token|api_?key|access_?key|private_?key|credentials?)[A-Z0-9_.-]*["']?\s*[:=]\s*["']?(?P<secret>[^\s"'`,;]{6,})"#,
+                Some(looks_like_secret_value),
+            ),
+            detector(
+                SecretKind::Email,
+                r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}\b",
+                None,
+            ),
+            detector(
+                SecretKind::HighEntropy,
+                // Starts with an alphanumeric so that diff markers stay in place
+                r"[A-Za-z0-9][A-Za-z0-9+/_-]{23,}={0,2}",
+                Some(is_high_entropy),
+            ),
+        ]
+    })
+}
+
+/// Assigned values that look like a literal secret rather than code or a reference such as
+/// `${API_KEY}`, `env::var("TOKEN")` or `Option<String>`
+fn looks_like_secret_value(value: &str) -> bool {
+    if value.starts_with("[REDACTED") || value.starts_with('$') || value.contains(['(', ')', '<', '>', '{', '}', '[', ']', '&']) {
+        return false;
+    }
+    // Numbers such as `max_tokens = 128_000` are limits, not secrets
+    if value.chars().all(|c| c.is_ascii_digit() || c == '_' || c == '.') {
+        return false;
+    }
+    // Identifiers like `String` or `self.token` have neither digits nor symbols
+    value.chars().any(|c| c.is_ascii_digit() || "!@#%^*+/=~-".contains(c))
+}
+
+/// Code identifiers such as `OpenAICompatibleProvider2Config` split into a few long words, while
+/// random tokens change case or switch between letters and digits every couple of characters
+fn is_identifier(value: &str) -> bool {
+    if !value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return false;
+    }
+    let chars: Vec<char> = value.chars().collect();
+    let breaks = chars
+        .windows(2)
+        .filter(|pair| {
+            let (a, b) = (pair[0], pair[1]);
+            (a.is_ascii_lowercase() && b.is_ascii_uppercase()) || a.is_ascii_digit() != b.is_ascii_digit() || b == '_'
+        })
+        .count();
+    chars.len() >= 4 * (breaks + 1)
+}
+
+/// Mixed-case alphanumerics with the character spread of random data
+fn is_high_entropy(value: &str) -> bool {
+    let has = |check: fn(&char) -> bool| value.chars().any(|c| check(&c));
+    if !(has(char::is_ascii_lowercase) && has(char::is_ascii_uppercase) && has(char::is_ascii_digit)) || is_identifier(value) {
+        return false;
+    }
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    for c in value.chars() {
+        *counts.entry(c).or_default() += 1;
+    }
+    let len = value.chars().count() as f64;
+    let entropy: f64 = counts.values()
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum();
+    entropy >= 4.0
+}
+
+/// File named by the closest `diff --git` header before `offset`
+fn file_at(text: &str, offset: usize) -> Option<String> {
+    let header_start = text[..offset].rfind("diff --git ")?;
+    let header = text[header_start..].lines().next()?;
+    header.rsplit_once(" b/").map(|(_, path)| path.to_string())
+}
+
+/// Masks secrets, leaving matches of the allow-list alone
+#[derive(Debug, Default)]
+pub struct Redactor {
+    allow: Vec<Regex>,
+}
+
+impl Redactor {
+    /// `allow` holds regular expressions for values that must not be masked
+    pub fn new(allow: &[String]) -> Result<Self, regex::Error> {
+        Ok(Self { allow: allow.iter().map(|pattern| Regex::new(pattern)).collect::<Result<_, _>>()? })
+    }
+
+    pub fn redact(&self, text: &str) -> Redacted {
+        let mut text = text.to_string();
+        let mut findings = Vec::new();
+
+        for detector in detectors() {
+            let mut out = String::with_capacity(text.len());
+            let mut last = 0;
+            for caps in detector.pattern.captures_iter(&text) {
+                let secret = caps.name("secret").unwrap_or_else(|| caps.get(0).unwrap());
+                let value = secret.as_str();
+                if detector.confirm.is_some_and(|confirm| !confirm(value))
+                    || self.allow.iter().any(|allow| allow.is_match(value))
+                {
+                    continue;
+                }
+                findings.push(Finding { kind: detector.kind, file: file_at(&text, secret.start()) });
+                out.push_str(&text[last..secret.start()]);
+                out.push_str(&detector.kind.placeholder());
+                last = secret.end();
+            }
+            if last > 0 {
+                out.push_str(&text[last..]);
+                text = out;
+            }
+        }
+
+        Redacted { text, findings }
+    }
+
+    /// Apply `policy` to `text`
+    pub fn apply(&self, policy: RedactionPolicy, text: &str) -> Result<Redacted, Blocked> {
+        if policy == RedactionPolicy::Off {
+            return Ok(Redacted { text: text.to_string(), findings: Vec::new() });
+        }
+        let redacted = self.redact(text);
+        if policy == RedactionPolicy::Block && !redacted.findings.is_empty() {
+            return Err(Blocked { findings: redacted.findings });
+        }
+        Ok(redacted)
+    }
+}
@@ -0,0 +1,309 @@
+use std::error::Error;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::changeset::{FileDiff, Hunk};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    /// Models do not always stick to the four names
+    fn parse(text: &str) -> Self {
+        match text.trim().to_lowercase().as_str() {
+            "critical" | "blocker" => Severity::Critical,
+            "high" | "error" | "major" => Severity::High,
+            "medium" | "moderate" | "warning" => Severity::Medium,
+            _ => Severity::Low,
+        }
+    }
+
+    /// SARIF result level
+    fn level(&self) -> &'static str {
+        match self {
+            Severity::Critical | Severity::High => "error",
+            Severity::Medium => "warning",
+            Severity::Low => "note",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    Bug,
+    Security,
+    Perf,
+    Style,
+}
+
+impl Category {
+    fn parse(text: &str) -> Self {
+        let text = text.to_lowercase();
+        if text.contains("secur") {
+            Category::Security
+        } else if text.contains("perf") {
+            Category::Perf
+        } else if ["style", "readab", "maintain", "naming"].iter().any(|word| text.contains(word)) {
+            Category::Style
+        } else {
+            Category::Bug
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Category::Bug => "bug",
+            Category::Security => "security",
+            Category::Perf => "perf",
+            Category::Style => "style",
+        }
+    }
+}
+
+/// A problem the model found in a changed file
+#[derive(Debug, Clone, Serialize)]
+pub struct ReviewFinding {
+    pub file: String,
+    /// Lines in the new version of the file, within one of the diff's hunks
+    pub start_line: u32,
+    pub end_line: u32,
+    pub severity: Severity,
+    pub category: Category,
+    pub message: String,
+    pub suggestion: Option<String>,
+    /// Header of the hunk the lines belong to
+    pub hunk: String,
+}
+
+/// How a review is written out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    #[default]
+    Text,
+    Json,
+    Sarif,
+}
+
+/// `file`'s hunks with the new line number in front of every line that has one, so that
+/// findings can refer to them
+pub fn annotate(file: &FileDiff) -> String {
+    let mut out = format!("File: {}\n", file.path);
+    for hunk in &file.hunks {
+        out.push_str(&hunk.header);
+        out.push('\n');
+        let mut line_number = hunk.new_start;
+        for line in &hunk.lines {
+            match line.origin {
+                '+' | ' ' => {
+                    out.push_str(&format!("{:>5} {}{}", line_number, line.origin, line.content));
+                    line_number += 1;
+                }
+                '-' => out.push_str(&format!("{:>5} -{}", "", line.content)),
+                _ => continue,
+            }
+            if !line.content.ends_with('\n') {
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+#[derive(Deserialize)]
+struct RawFinding {
+    #[serde(alias = "line")]
+    start_line: Option<u32>,
+    end_line: Option<u32>,
+    #[serde(default)]
+    severity: String,
+    #[serde(default)]
+    category: String,
+    #[serde(alias = "title", alias = "description")]
+    message: String,
+    suggestion: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawReview {
+    Wrapped { findings: Vec<RawFinding> },
+    List(Vec<RawFinding>),
+}
+
+/// Read the model's JSON answer for `file`, moving every finding onto the lines of a hunk
+pub fn parse_findings(text: &str, file: &FileDiff) -> Result<Vec<ReviewFinding>, Box<dyn Error>> {
+    let start = text.find(['{', '[']).ok_or("The answer contains no JSON")?;
+    let end = text.rfind(['}', ']']).filter(|&end| end >= start).ok_or("The answer contains no JSON")?;
+    let raw = match serde_json::from_str(&text[start..=end])? {
+        RawReview::Wrapped { findings } | RawReview::List(findings) => findings,
+    };
+
+    Ok(raw.into_iter()
+        .filter_map(|finding| {
+            let start_line = finding.start_line?;
+            let end_line = finding.end_line.unwrap_or(start_line).max(start_line);
+            let (hunk, start_line, end_line) = place_in_hunk(&file.hunks, start_line, end_line)?;
+            Some(ReviewFinding {
+                file: file.path.clone(),
+                start_line,
+                end_line,
+                severity: Severity::parse(&finding.severity),
+                category: Category::parse(&finding.category),
+                message: finding.message.trim().to_string(),
+                suggestion: finding.suggestion.map(|s| s.trim().to_string()).filter(|s| !s.is_empty()),
+                hunk: hunk.header.clone(),
+            })
+        })
+        .collect())
+}
+
+/// New-file lines a hunk covers; a pure deletion covers the line it follows
+fn new_range(hunk: &Hunk) -> (u32, u32) {
+    let start = hunk.new_start.max(1);
+    (start, start + hunk.new_lines.saturating_sub(1))
+}
+
+/// The hunk overlapping `start..=end`, with the lines clamped to it. Lines outside every hunk
+/// go to the closest one.
+fn place_in_hunk(hunks: &[Hunk], start: u32, end: u32) -> Option<(&Hunk, u32, u32)> {
+    let distance = |hunk: &Hunk| {
+        let (first, last) = new_range(hunk);
+        if end < first { first - end } else { start.saturating_sub(last) }
+    };
+    let hunk = hunks.iter().min_by_key(|hunk| distance(hunk))?;
+    let (first, last) = new_range(hunk);
+    let start = start.clamp(first, last);
+    Some((hunk, start, end.clamp(start, last)))
+}
+
+/// Findings at or above `threshold`
+pub fn count_at_least(findings: &[ReviewFinding], threshold: Severity) -> usize {
+    findings.iter().filter(|finding| finding.severity >= threshold).count()
+}
+
+/// Findings grouped by file, most severe first within each file
+pub fn render_text(findings: &[ReviewFinding]) -> String {
+    if findings.is_empty() {
+        return "No findings.\n".to_string();
+    }
+
+    let mut sorted: Vec<&ReviewFinding> = findings.iter().collect();
+    sorted.sort_by(|a, b| {
+        a.file.cmp(&b.file)
+            .then_with(|| b.severity.cmp(&a.severity))
+            .then_with(|| a.start_line.cmp(&b.start_line))
+    });
+
+    let mut out = String::new();
+    let mut current_file = None;
+    for finding in sorted {
+        if current_file != Some(&finding.file) {
+            if current_file.is_some() {
+                out.push('\n');
+            }
+            out.push_str(&format!("📄 {}\n", finding.file));
+            current_file = Some(&finding.file);
+        }
+        let lines = if finding.start_line == finding.end_line {
+            format!("line {}", finding.start_line)
+        } else {
+            format!("lines {}-{}", finding.start_line, finding.end_line)
+        };
+        out.push_str(&format!(
+            "  {} {} {} ({}): {}\n",
+            severity_icon(finding.severity),
+            finding.severity,
+            finding.category.label(),
+            lines,
+            finding.message
+        ));
+        if let Some(suggestion) = &finding.suggestion {
+            out.push_str(&format!("     💡 {}\n", suggestion));
+        }
+    }
+
+    let counts: Vec<String> = [Severity::Critical, Severity::High, Severity::Medium, Severity::Low]
+        .into_iter()
+        .map(|severity| (severity, findings.iter().filter(|f| f.severity == severity).count()))
+        .filter(|(_, count)| *count > 0)
+        .map(|(severity, count)| format!("{} {}", count, severity))
+        .collect();
+    out.push_str(&format!("\n{} finding(s): {}\n", findings.len(), counts.join(", ")));
+    out
+}
+
+fn severity_icon(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical => "⛔",
+        Severity::High => "🔴",
+        Severity::Medium => "🟠",
+        Severity::Low => "🔵",
+    }
+}
+
+pub fn render_json(findings: &[ReviewFinding]) -> Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(&json!({ "findings": findings }))
+}
+
+/// SARIF 2.1.0 log, as read by code scanning tools
+pub fn render_sarif(findings: &[ReviewFinding]) -> Result<String, serde_json::Error> {
+    let rules: Vec<_> = [Category::Bug, Category::Security, Category::Perf, Category::Style]
+        .iter()
+        .map(|category| json!({ "id": category.label(), "name": category.label() }))
+        .collect();
+    let results: Vec<_> = findings.iter()
+        .map(|finding| {
+            let mut text = finding.message.clone();
+            if let Some(suggestion) = &finding.suggestion {
+                text.push_str(&format!("\n\nSuggestion: {}", suggestion));
+            }
+            json!({
+                "ruleId": finding.category.label(),
+                "level": finding.severity.level(),
+                "message": { "text": text },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": finding.file },
+                        "region": { "startLine": finding.start_line, "endLine": finding.end_line },
+                    }
+                }],
+                "properties": { "severity": finding.severity },
+            })
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }],
+    }))
+}
@@ -0,0 +1,328 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::Deserialize;
+
+use crate::changeset::{self, DiffFilter, DiffRule, RuleAction};
+use crate::commit::SigningMode;
+use crate::git_analysis::{GitAnalyzerImpl, Task};
+use crate::redact::{RedactionPolicy, Redactor};
+use crate::providers::{HttpSettings, OpenAICompatibleConfig};
+
+/// Name of the per-repository configuration file, looked up in the repository root
+pub const REPO_CONFIG_FILE: &str = ".merit.toml";
+
+/// Persistent configuration read from `~/.config/merit/config.toml` and `.merit.toml`.
+/// Values from the repository file win over the global file; CLI flags win over both.
+/// Provider endpoints and looser redaction are only read from the global file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Default provider, or a comma-separated fallback chain
+    pub provider: Option<String>,
+    /// Default model for every task
+    pub model: Option<String>,
+    pub tasks: TaskTable,
+    pub commit: CommitSettings,
+    pub diff: DiffSettings,
+    pub redaction: RedactionSettings,
+    pub http: HttpTable,
+    /// Additional OpenAI-compatible endpoints
+    pub providers: Vec<OpenAICompatibleConfig>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TaskTable {
+    pub commit_message: TaskSettings,
+    pub file_analysis: TaskSettings,
+    pub contributor_analysis: TaskSettings,
+    pub pull_request: TaskSettings,
+    pub changelog: TaskSettings,
+    pub review: TaskSettings,
+}
+
+/// Overrides for a single task
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TaskSettings {
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    /// System prompt replacing the built-in one
+    pub prompt: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CommitSettings {
+    /// Types offered by the "Edit commit type" menu
+    pub types: Option<Vec<CommitType>>,
+    /// Scopes offered alongside the ones inferred from the changed paths
+    pub scopes: Vec<String>,
+    /// `off`, `gpg`, `ssh`, or `auto` to follow `commit.gpgsign` (the default)
+    pub signing: Option<SigningMode>,
+    /// Describe and commit only the staged changes instead of the whole working tree
+    pub staged_only: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommitType {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// How diffs are prepared for the model
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiffSettings {
+    /// Token budget for a single request, below the model's context window
+    pub max_tokens: Option<usize>,
+    /// Globs of files listed without their content, on top of the built-in ones
+    pub ignore: Vec<String>,
+    /// Rules checked before the built-in lockfile and generated-file rules
+    pub rules: Vec<DiffRule>,
+    /// Files with a larger diff are listed without their content
+    pub max_file_bytes: Option<usize>,
+}
+
+/// Masking of secrets before text is sent to a provider
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedactionSettings {
+    pub policy: Option<RedactionPolicy>,
+    /// Regular expressions for values that must never be masked
+    pub allow: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpTable {
+    pub timeout_secs: Option<u64>,
+    pub max_retries: Option<u32>,
+}
+
+impl Settings {
+    /// Global settings merged with the settings of the repository at `repo_path`
+    pub fn load(repo_path: &Path) -> Result<Self, Box<dyn Error>> {
+        let global = match global_config_path() {
+            Some(path) => Self::load_file(&path)?,
+            None => Self::default(),
+        };
+        let repo_file = repo_path.join(REPO_CONFIG_FILE);
+        let repo = Self::load_file(&repo_file)?;
+        repo.check_untrusted(&global, &repo_file)?;
+        Ok(global.merge(repo))
+    }
+
+    /// A cloned repository must not point the user's API keys at another host or weaken redaction,
+    /// so endpoints and looser redaction are only accepted from the global file
+    fn check_untrusted(&self, global: &Self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let global_path = global_config_path()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| "the global configuration".to_string());
+        if !self.providers.is_empty() {
+            return Err(format!("{} may not declare [[providers]]; move them to {}", path.display(), global_path).into());
+        }
+        if let Some(policy) = self.redaction.policy {
+            if policy < global.redaction_policy() {
+                return Err(format!(
+                    "{} may not loosen the redaction policy to {}; set it in {}",
+                    path.display(),
+                    format!("{:?}", policy).to_lowercase(),
+                    global_path
+                )
+                .into());
+            }
+        }
+        if !self.redaction.allow.is_empty() {
+            return Err(format!("{} may not add redaction allow patterns; set them in {}", path.display(), global_path).into());
+        }
+        Ok(())
+    }
+
+    /// Settings from one file; a missing file yields the defaults
+    pub fn load_file(path: &Path) -> Result<Self, Box<dyn Error>> {
+        match fs::read_to_string(path) {
+            Ok(content) => {
+                let settings: Self = toml::from_str(&content)
+                    .map_err(|e| format!("Invalid configuration in {}: {}", path.display(), e))?;
+                let patterns = settings.diff.ignore.iter().chain(settings.diff.rules.iter().map(|rule| &rule.pattern));
+                for pattern in patterns {
+                    if glob::Pattern::new(pattern).is_err() {
+                        return Err(format!("Invalid pattern `{}` in {}", pattern, path.display()).into());
+                    }
+                }
+                if let Err(e) = Redactor::new(&settings.redaction.allow) {
+                    return Err(format!("Invalid redaction allow pattern in {}: {}", path.display(), e).into());
+                }
+                Ok(settings)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("Could not read {}: {}", path.display(), e).into()),
+        }
+    }
+
+    /// Overlay `other` on top of these settings
+    pub fn merge(self, other: Self) -> Self {
+        let mut providers = self.providers;
+        providers.extend(other.providers);
+        // Repository rules are checked first
+        let mut rules = other.diff.rules;
+        rules.extend(self.diff.rules);
+        let mut ignore = other.diff.ignore;
+        ignore.extend(self.diff.ignore);
+        let mut scopes = other.commit.scopes;
+        scopes.extend(self.commit.scopes);
+        let mut allow = other.redaction.allow;
+        allow.extend(self.redaction.allow);
+        Self {
+            provider: other.provider.or(self.provider),
+            model: other.model.or(self.model),
+            tasks: TaskTable {
+                commit_message: self.tasks.commit_message.merge(other.tasks.commit_message),
+                file_analysis: self.tasks.file_analysis.merge(other.tasks.file_analysis),
+                contributor_analysis: self.tasks.contributor_analysis.merge(other.tasks.contributor_analysis),
+                pull_request: self.tasks.pull_request.merge(other.tasks.pull_request),
+                changelog: self.tasks.changelog.merge(other.tasks.changelog),
+                review: self.tasks.review.merge(other.tasks.review),
+            },
+            commit: CommitSettings {
+                types: other.commit.types.or(self.commit.types),
+                scopes,
+                signing: other.commit.signing.or(self.commit.signing),
+                staged_only: other.commit.staged_only.or(self.commit.staged_only),
+            },
+            diff: DiffSettings {
+                max_tokens: other.diff.max_tokens.or(self.diff.max_tokens),
+                ignore,
+                rules,
+                max_file_bytes: other.diff.max_file_bytes.or(self.diff.max_file_bytes),
+            },
+            redaction: RedactionSettings {
+                policy: other.redaction.policy.or(self.redaction.policy),
+                allow,
+            },
+            http: HttpTable {
+                timeout_secs: other.http.timeout_secs.or(self.http.timeout_secs),
+                max_retries: other.http.max_retries.or(self.http.max_retries),
+            },
+            providers,
+        }
+    }
+
+    pub fn task(&self, task: Task) -> &TaskSettings {
+        match task {
+            Task::CommitMessage => &self.tasks.commit_message,
+            Task::FileAnalysis => &self.tasks.file_analysis,
+            Task::ContributorAnalysis => &self.tasks.contributor_analysis,
+            Task::PullRequest => &self.tasks.pull_request,
+            Task::Changelog => &self.tasks.changelog,
+            Task::Review => &self.tasks.review,
+        }
+    }
+
+    /// Apply the temperatures and prompts configured for each task
+    pub fn configure_analyzer(&self, mut analyzer: GitAnalyzerImpl) -> GitAnalyzerImpl {
+        for task in Task::ALL {
+            let settings = self.task(task);
+            if let Some(temperature) = settings.temperature {
+                analyzer = analyzer.with_temperature(task, temperature);
+            }
+            if let Some(prompt) = &settings.prompt {
+                analyzer = analyzer.with_prompt(task, prompt.clone());
+            }
+        }
+        if let Some(max_tokens) = self.diff.max_tokens {
+            analyzer = analyzer.with_max_input_tokens(max_tokens);
+        }
+        analyzer
+    }
+
+    /// HTTP settings from the configuration, overridden by environment variables
+    pub fn http_settings(&self) -> HttpSettings {
+        let mut settings = HttpSettings::default();
+        if let Some(secs) = self.http.timeout_secs {
+            settings.timeout = Duration::from_secs(secs);
+        }
+        if let Some(retries) = self.http.max_retries {
+            settings.max_retries = retries;
+        }
+        settings.with_env_overrides()
+    }
+
+    pub fn commit_types(&self) -> Vec<CommitType> {
+        self.commit.types.clone().unwrap_or_else(default_commit_types)
+    }
+
+    pub fn commit_scopes(&self) -> &[String] {
+        &self.commit.scopes
+    }
+
+    pub fn signing(&self) -> SigningMode {
+        self.commit.signing.unwrap_or_default()
+    }
+
+    /// Configured rules, then ignore globs, then the built-in rules
+    pub fn diff_filter(&self) -> DiffFilter {
+        let mut rules = self.diff.rules.clone();
+        rules.extend(self.diff.ignore.iter().map(|pattern| DiffRule::new(pattern, RuleAction::Skip)));
+        rules.extend(changeset::default_rules());
+        DiffFilter {
+            rules,
+            max_file_bytes: self.diff.max_file_bytes.unwrap_or(changeset::DEFAULT_MAX_FILE_BYTES),
+        }
+    }
+
+    pub fn redaction_policy(&self) -> RedactionPolicy {
+        self.redaction.policy.unwrap_or_default()
+    }
+
+    /// Redactor honouring the allow-list; patterns were validated when the settings were loaded
+    pub fn redactor(&self) -> Redactor {
+        Redactor::new(&self.redaction.allow).unwrap_or_default()
+    }
+
+    pub fn staged_only(&self) -> bool {
+        self.commit.staged_only.unwrap_or(false)
+    }
+}
+
+impl TaskSettings {
+    fn merge(self, other: Self) -> Self {
+        Self {
+            model: other.model.or(self.model),
+            temperature: other.temperature.or(self.temperature),
+            prompt: other.prompt.or(self.prompt),
+        }
+    }
+}
+
+fn default_commit_types() -> Vec<CommitType> {
+    [
+        ("feat", "✨ New feature"),
+        ("fix", "🐛 Bug fix"),
+        ("docs", "📚 Documentation"),
+        ("style", "💅 Formatting"),
+        ("refactor", "♻️ Code restructure"),
+        ("perf", "⚡ Performance"),
+        ("test", "🧪 Testing"),
+        ("build", "📦 Build system or dependencies"),
+        ("ci", "👷 Continuous integration"),
+        ("chore", "🔧 Maintenance"),
+        ("revert", "⏪ Revert a previous commit"),
+    ]
+    .into_iter()
+    .map(|(name, description)| CommitType { name: name.to_string(), description: description.to_string() })
+    .collect()
+}
+
+/// `$XDG_CONFIG_HOME/merit/config.toml`, falling back to `~/.config/merit/config.toml`
+pub fn global_config_path() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("merit").join("config.toml"))
+}
@@ -1,12 +1,15 @@
 use std::error::Error;
-use dialoguer::{theme::ColorfulTheme, Select, Input};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, MultiSelect, Select};
 use indicatif::{ProgressBar, ProgressStyle};
 use termimad::{MadSkin, gray, StyledChar};
 
-use crate::modes::Mode;
+use crate::git_analysis::{Task, TaskModels};
+use crate::modes::{Mode, DEFAULT_MIN_SCORE};
+use crate::providers::{http, Provider, ProviderError};
+use crate::redact::{Finding, SecretKind};
+use crate::review::ReportFormat;
 
-/// Renders markdown text in the terminal with proper styling
-pub fn print_markdown(text: &str) {
+fn markdown_skin() -> MadSkin {
     let mut skin = MadSkin::default();
     // Configure markdown styling
     skin.set_headers_fg(gray(255));  // Bright white for headers
@@ -15,19 +18,115 @@ pub fn print_markdown(text: &str) {
     skin.bullet = StyledChar::from_fg_char(gray(180), '•');
     skin.quote_mark = StyledChar::from_fg_char(gray(180), '▐');
     skin.code_block.set_fg(gray(71)); // Light green for code blocks
-    
+    skin
+}
+
+/// Renders markdown text in the terminal with proper styling
+pub fn print_markdown(text: &str) {
     // Add a newline before and after for better spacing
     println!();
-    skin.print_text(text);
+    markdown_skin().print_text(text);
     println!();
 }
 
+/// Renders markdown progressively as chunks arrive, one finished block at a time
+pub struct MarkdownStream {
+    skin: MadSkin,
+    pending: String,
+}
+
+impl MarkdownStream {
+    pub fn new() -> Self {
+        println!();
+        Self { skin: markdown_skin(), pending: String::new() }
+    }
+
+    /// Append a chunk and print every block that can no longer change
+    pub fn push(&mut self, chunk: &str) {
+        self.pending.push_str(chunk);
+        if let Some(end) = last_block_end(&self.pending) {
+            let block: String = self.pending.drain(..end).collect();
+            self.skin.print_text(&block);
+        }
+    }
+
+    /// Print whatever is left once the stream has ended
+    pub fn finish(self) {
+        if !self.pending.trim().is_empty() {
+            self.skin.print_text(&self.pending);
+        }
+        println!();
+    }
+}
+
+impl Default for MarkdownStream {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Byte offset just past the last blank line that is not inside a code fence
+fn last_block_end(text: &str) -> Option<usize> {
+    let mut in_fence = false;
+    let mut offset = 0;
+    let mut end = None;
+    for line in text.split_inclusive('\n') {
+        offset += line.len();
+        if !line.ends_with('\n') {
+            break;
+        }
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        } else if !in_fence && line.trim().is_empty() {
+            end = Some(offset);
+        }
+    }
+    end
+}
+
 /// Prints a section header with a title
 pub fn print_section(title: &str) {
     println!("\n{}", title);
     println!("{}\n", "═".repeat(title.chars().count()));
 }
 
+/// Explains why a provider request failed and what the user can do about it
+pub fn print_provider_error(error: &ProviderError) {
+    print_section("⚠️ Provider Error");
+    println!("{}", error);
+    if let Some(hint) = error.hint() {
+        println!("💡 {}", hint);
+    }
+    println!();
+}
+
+/// Lists the secrets masked in a request, grouped by file
+pub fn print_redaction_report(findings: &[Finding], blocked: bool) {
+    if blocked {
+        print_section("⛔ Request Blocked: Possible Secrets Found");
+    } else {
+        print_section("🔒 Secrets Redacted Before Sending");
+    }
+
+    let mut groups: Vec<(Option<&str>, SecretKind, usize)> = Vec::new();
+    for finding in findings {
+        let file = finding.file.as_deref();
+        match groups.iter_mut().find(|(f, kind, _)| *f == file && *kind == finding.kind) {
+            Some(group) => group.2 += 1,
+            None => groups.push((file, finding.kind, 1)),
+        }
+    }
+    for (file, kind, count) in groups {
+        let location = file.map(|file| format!(" in {}", file)).unwrap_or_default();
+        println!("  • {} × {}{}", count, kind.label(), location);
+    }
+
+    if blocked {
+        println!("\n💡 Remove the secrets, add them to `redaction.allow`, or set `redaction.policy = \"redact\"`.");
+    }
+    println!();
+}
+
 /// Prints a subsection header with a title
 pub fn print_subsection(title: &str) {
     println!("\n{}", title);
@@ -43,6 +142,21 @@ pub fn create_spinner(message: &str) -> Result<ProgressBar, Box<dyn Error>> {
     );
     spinner.enable_steady_tick(std::time::Duration::from_millis(100));
     spinner.set_message(format!("{}...", message));
+
+    // Show provider retries on the spinner that is waiting for them
+    let retry_spinner = spinner.clone();
+    let message = message.to_string();
+    http::set_retry_observer(Some(Box::new(move |notice| {
+        retry_spinner.set_message(format!(
+            "{} (retry {}/{} in {}s: {})...",
+            message,
+            notice.attempt,
+            notice.max_retries,
+            notice.wait.as_secs_f32().ceil(),
+            notice.error,
+        ));
+    })));
+
     Ok(spinner)
 }
 
@@ -54,22 +168,76 @@ pub fn show_selection_menu<T: AsRef<str> + ToString>(prompt: &str, items: &[T],
         .interact()?)
 }
 
+/// Returns the indices of the checked items
+pub fn show_multi_selection<T: ToString>(prompt: &str, items: &[T], checked: &[bool]) -> Result<Vec<usize>, Box<dyn Error>> {
+    Ok(MultiSelect::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .items(items)
+        .defaults(checked)
+        .interact()?)
+}
+
+pub fn confirm(prompt: &str, default: bool) -> Result<bool, Box<dyn Error>> {
+    Ok(Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .default(default)
+        .interact()?)
+}
+
 pub async fn select_mode() -> Result<Mode, Box<dyn Error>> {
     let modes = [
-        Mode::CommitMessage.description(),
-        Mode::FileAnalysis.description(),
-        Mode::ContributorAnalysis.description(),
+        Mode::CommitMessage { staged_only: false, no_verify: false }.description(),
+        Mode::FileAnalysis { rev: None, combined: false }.description(),
+        Mode::ContributorAnalysis { author: None }.description(),
+        Mode::PullRequestDescription { base: None, head: None, output: None }.description(),
+        Mode::Changelog { from: None, to: None, summary: false, prepend: None }.description(),
+        Mode::Review { staged_only: false, format: ReportFormat::Text, output: None, fail_on: None }.description(),
+        Mode::Reword { from: None, min_score: DEFAULT_MIN_SCORE, force: false }.description(),
     ];
     
     let selection = show_selection_menu("What would you like to do?", &modes, 0)?;
     
     Ok(match selection {
-        0 => Mode::CommitMessage,
-        1 => Mode::FileAnalysis,
-        _ => Mode::ContributorAnalysis,
+        0 => Mode::CommitMessage { staged_only: false, no_verify: false },
+        1 => Mode::FileAnalysis { rev: None, combined: false },
+        2 => Mode::ContributorAnalysis { author: None },
+        3 => Mode::PullRequestDescription { base: None, head: None, output: None },
+        4 => Mode::Changelog { from: None, to: None, summary: false, prepend: None },
+        5 => Mode::Review { staged_only: false, format: ReportFormat::Text, output: None, fail_on: None },
+        _ => Mode::Reword { from: None, min_score: DEFAULT_MIN_SCORE, force: false },
     })
 }
 
+/// Lets the user pick one model for every task, or a separate model per task
+pub fn select_models(provider: &dyn Provider) -> Result<TaskModels, Box<dyn Error>> {
+    let models = provider.models();
+    if models.len() < 2 {
+        return Ok(TaskModels::uniform(provider.default_model()));
+    }
+
+    let mut items = models.clone();
+    items.push("🎛️ Choose a model per task".to_string());
+    let selection = show_selection_menu(&format!("Select a {} model", provider.name()), &items, 0)?;
+    if selection < models.len() {
+        return Ok(TaskModels::uniform(models[selection].as_str()));
+    }
+
+    let mut task_models = TaskModels::uniform(provider.default_model());
+    for task in Task::ALL {
+        let selection = show_selection_menu(&format!("Model for {}", task.description()), &models, 0)?;
+        task_models.set(task, models[selection].clone());
+    }
+    Ok(task_models)
+}
+
+pub fn input(prompt: &str, default: &str) -> Result<String, Box<dyn Error>> {
+    Ok(Input::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .default(default.into())
+        .allow_empty(true)
+        .interact()?)
+}
+
 pub fn get_repository_path(default: &str) -> Result<String, Box<dyn Error>> {
     let path: String = Input::with_theme(&ColorfulTheme::default())
         .with_prompt("Enter repository path")
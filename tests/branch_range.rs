@@ -0,0 +1,77 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::{commit_file, commit_index, init_repo};
+use merit_cli_demo::changeset::DiffFilter;
+use merit_cli_demo::git::{get_range, get_revision, MergeDiff};
+
+#[test]
+fn range_has_branch_commits_and_diff_since_merge_base() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = init_repo(dir.path());
+    commit_file(&repo, "README.md", "hello\n", "chore: initial commit");
+    let base = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.branch("feature", &base, false).unwrap();
+
+    // The base moves on after the branch was created
+    commit_file(&repo, "main.txt", "main\n", "docs: only on main");
+    repo.set_head("refs/heads/feature").unwrap();
+    repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force())).unwrap();
+    commit_file(&repo, "feature.txt", "one\n", "feat: add feature");
+    commit_file(&repo, "feature.txt", "one\ntwo\n", "fix: extend feature\n\nWith a body.");
+
+    let base_branch = if repo.find_branch("main", git2::BranchType::Local).is_ok() { "main" } else { "master" };
+    let range = get_range(&repo, base_branch, "feature").unwrap();
+
+    let messages: Vec<&str> = range.commits.iter().map(|commit| commit.message.as_str()).collect();
+    assert_eq!(messages, ["feat: add feature", "fix: extend feature\n\nWith a body."]);
+    let rendered = range.render(&DiffFilter::default());
+    assert!(rendered.contains("+two"));
+    assert!(!rendered.contains("main.txt"));
+    assert!(get_range(&repo, "feature", base_branch).is_ok());
+    assert!(get_range(&repo, "feature", "feature").is_err());
+}
+
+#[test]
+fn revisions_diff_against_first_parent_or_all_parents_of_a_merge() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = init_repo(dir.path());
+    commit_file(&repo, "README.md", "hello\n", "chore: initial commit");
+    let base = repo.head().unwrap().peel_to_commit().unwrap();
+    commit_file(&repo, "main.txt", "main\n", "docs: only on main");
+    let main = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.branch("feature", &base, false).unwrap();
+    repo.set_head("refs/heads/feature").unwrap();
+    repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force())).unwrap();
+    commit_file(&repo, "feature.txt", "one\n", "feat: add feature");
+    commit_file(&repo, "feature.txt", "one\ntwo\n", "fix: extend feature");
+    let feature = repo.head().unwrap().peel_to_commit().unwrap();
+
+    let range = get_revision(&repo, "feature~1..feature", MergeDiff::FirstParent).unwrap();
+    assert_eq!(range.commits.len(), 1);
+    assert!(range.render(&DiffFilter::default()).contains("+two"));
+
+    // A merge that also edits a file neither side touched the same way
+    let workdir = repo.workdir().unwrap();
+    fs::write(workdir.join("main.txt"), "main\n").unwrap();
+    fs::write(workdir.join("README.md"), "hello\nmerged\n").unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("main.txt")).unwrap();
+    index.add_path(Path::new("README.md")).unwrap();
+    index.write().unwrap();
+    commit_index(&repo, "Merge main", &[&feature, &main]);
+
+    let first_parent = get_revision(&repo, "HEAD", MergeDiff::FirstParent).unwrap();
+    assert!(first_parent.commits[0].merge);
+    let paths: Vec<&str> = first_parent.changes.files.iter().map(|file| file.path.as_str()).collect();
+    assert_eq!(paths, ["README.md", "main.txt"]);
+
+    let combined = get_revision(&repo, "HEAD", MergeDiff::Combined).unwrap();
+    assert_eq!(combined.changes.files.len(), 1);
+    let readme = &combined.changes.files[0];
+    assert_eq!(readme.path, "README.md");
+    assert!(readme.hunks[0].header.ends_with("against parent 1"));
+    assert!(readme.hunks[1].header.ends_with("against parent 2"));
+}
@@ -0,0 +1,37 @@
+use merit_cli_demo::changelog::{prepend, Release};
+use merit_cli_demo::git::RangeCommit;
+
+fn commit(id: &str, message: &str) -> RangeCommit {
+    RangeCommit { id: id.to_string(), message: message.to_string(), merge: false }
+}
+
+#[test]
+fn release_groups_commits_and_replaces_unreleased_section() {
+    let commits = [
+        commit("a1", "feat: add export"),
+        commit("b2", "fix(parser): handle empty input"),
+        commit("c3", "docs: describe export"),
+        commit("d4", "feat(api)!: drop v1 endpoints"),
+        commit("e5", "Update README"),
+    ];
+    let release = Release::new(Some("v1.2.0"), Some("2026-10-18"), &commits);
+
+    assert_eq!(release.skipped, 2);
+    assert_eq!(
+        release.render(),
+        "## [1.2.0] - 2026-10-18\n\n\
+         ### Added\n\n\
+         - **Breaking:** **api:** drop v1 endpoints (d4)\n\
+         - add export (a1)\n\n\
+         ### Fixed\n\n\
+         - **parser:** handle empty input (b2)\n"
+    );
+
+    let existing = "# Changelog\n\n## [Unreleased]\n\n### Added\n\n- old entry (f6)\n\n## [1.1.0] - 2026-01-01\n\n### Fixed\n\n- earlier fix (g7)\n\n[1.1.0]: https://example.com/v1.1.0\n";
+    let unreleased = Release::new(None, None, &commits[..1]);
+    let updated = prepend(Some(existing), &unreleased);
+
+    assert!(!updated.contains("old entry"));
+    assert!(updated.starts_with("# Changelog\n\n## [Unreleased]\n\n### Added\n\n- add export (a1)\n\n## [1.1.0]"));
+    assert!(updated.ends_with("- earlier fix (g7)\n\n[1.1.0]: https://example.com/v1.1.0\n"));
+}
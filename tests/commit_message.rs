@@ -0,0 +1,39 @@
+use merit_cli_demo::commit_message::{infer_scopes, strip_comments, CommitMessage, Footer};
+
+#[test]
+fn model_output_is_parsed_and_rendered_with_wrapped_body() {
+    let output = "```\nfeat(api)!: drop the v1 endpoints\n\nThe v1 endpoints have been deprecated for a year and keeping them around doubles the work for every change to the request handling.\n\nBREAKING CHANGE: clients must move to /v2\nRefs: #42\n```";
+
+    let message = CommitMessage::parse(output);
+
+    assert_eq!(message.commit_type, "feat");
+    assert_eq!(message.scope.as_deref(), Some("api"));
+    assert!(message.breaking);
+    assert_eq!(message.description, "drop the v1 endpoints");
+    assert_eq!(message.footers, vec![
+        Footer::new("BREAKING CHANGE", "clients must move to /v2"),
+        Footer::new("Refs", "#42"),
+    ]);
+    assert_eq!(
+        message.to_string(),
+        "feat(api)!: drop the v1 endpoints\n\n\
+         The v1 endpoints have been deprecated for a year and keeping them around\n\
+         doubles the work for every change to the request handling.\n\n\
+         BREAKING CHANGE: clients must move to /v2\nRefs: #42"
+    );
+}
+
+#[test]
+fn scopes_are_inferred_from_crates_and_modules() {
+    let paths = ["crates/parser/src/lexer.rs", "crates/parser/Cargo.toml", "src/git.rs", "src/git/diff.rs", "src/main.rs", "README.md", ".github/workflows/ci.yml"];
+
+    assert_eq!(infer_scopes(paths), vec!["git", "parser"]);
+}
+
+#[test]
+fn verbose_commit_template_counts_as_empty() {
+    let template = "\n# Please enter the commit message for your changes.\n# ------------------------ >8 ------------------------\n# Do not modify or remove the line above.\ndiff --git a/a.txt b/a.txt\n+added\n";
+
+    assert_eq!(strip_comments(template), "");
+    assert_eq!(strip_comments(&format!("fix: keep this\n{}", template)), "fix: keep this");
+}
@@ -0,0 +1,45 @@
+//! Temporary repositories shared by the integration tests
+// Each test binary uses only some of these helpers
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::Path;
+
+use git2::{Commit, Oid, Repository, Signature};
+
+/// Empty repository in `dir` with a committer identity configured
+pub fn init_repo(dir: &Path) -> Repository {
+    let repo = Repository::init(dir).unwrap();
+    let mut config = repo.config().unwrap();
+    config.set_str("user.name", "Test").unwrap();
+    config.set_str("user.email", "test@example.com").unwrap();
+    repo
+}
+
+/// Commit the index on top of `parents`, moving HEAD
+pub fn commit_index(repo: &Repository, message: &str, parents: &[&Commit]) -> Oid {
+    let mut index = repo.index().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = Signature::now("Test", "test@example.com").unwrap();
+    repo.commit(Some("HEAD"), &signature, &signature, message, &tree, parents).unwrap()
+}
+
+/// Write `content` to `path`, stage it and commit it on top of HEAD
+pub fn commit_file(repo: &Repository, path: &str, content: &str, message: &str) -> Oid {
+    fs::write(repo.workdir().unwrap().join(path), content).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(path)).unwrap();
+    index.write().unwrap();
+    let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+    commit_index(repo, message, &parent.iter().collect::<Vec<_>>())
+}
+
+/// Repository with one committed file and the given working tree edits
+pub fn repo_with_changes(dir: &Path, edits: &[(&str, &str)]) -> Repository {
+    let repo = init_repo(dir);
+    commit_file(&repo, "README.md", "hello\n", "chore: initial commit");
+    for (path, content) in edits {
+        fs::write(dir.join(path), content).unwrap();
+    }
+    repo
+}
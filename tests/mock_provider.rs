@@ -0,0 +1,134 @@
+mod common;
+
+use async_trait::async_trait;
+use common::repo_with_changes;
+use futures::StreamExt;
+use merit_cli_demo::git_analysis::{wrap_provider, FallbackAnalyzer, GitAnalyzer, GitAnalyzerImpl, Task};
+use merit_cli_demo::modes::Mode;
+use merit_cli_demo::providers::{MockProvider, Provider, ProviderError, ReplayProvider, TextStream};
+use merit_cli_demo::Config;
+
+#[tokio::test]
+async fn commit_message_comes_from_mock_provider() {
+    let provider = MockProvider::new().with_default_response("docs: greet the world");
+    let config = Config::new(wrap_provider(Box::new(provider), None), None);
+
+    let message = config.generate_commit_message("+hello world").await.unwrap();
+
+    assert_eq!(message.to_string(), "docs: greet the world");
+}
+
+#[tokio::test]
+async fn analyze_changes_explains_each_modified_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = repo_with_changes(dir.path(), &[("README.md", "hello\nworld\n")]);
+    let provider = MockProvider::new().with_default_response("Adds a second line.");
+    let config = Config::new(wrap_provider(Box::new(provider), None), None);
+
+    let analyses = config.analyze_changes(&repo, None).await.unwrap();
+
+    assert_eq!(analyses.len(), 1);
+    assert_eq!(analyses[0].path, "README.md");
+    assert_eq!(analyses[0].explanation, "Adds a second line.");
+}
+
+#[tokio::test]
+async fn headless_commit_sends_a_redacted_filtered_diff_and_commits() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = repo_with_changes(dir.path(), &[
+        ("README.md", "hello\nOPENAI_API_KEY=sk-proj-abcdefghijklmnopqrstuvwxyz0123\n"),
+        ("Cargo.lock", "# lockfile contents\n"),
+    ]);
+    let mut git_config = repo.config().unwrap();
+    git_config.set_str("user.name", "Test").unwrap();
+    git_config.set_str("user.email", "test@example.com").unwrap();
+    let provider = MockProvider::new().with_default_response("docs: document the API key");
+    let requests = provider.request_log();
+    let config = Config::new(wrap_provider(Box::new(provider), None), None).with_assume_yes(true);
+
+    Mode::CommitMessage { staged_only: false, no_verify: true }.execute(&config, &repo).await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    let prompt = &requests[0].user_input;
+    assert!(!prompt.contains("sk-proj-"));
+    assert!(prompt.contains("[REDACTED"));
+    assert!(prompt.contains("Cargo.lock") && !prompt.contains("# lockfile contents"));
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    assert_eq!(head.message(), Some("docs: document the API key\n"));
+    assert!(repo.statuses(None).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn recorded_exchanges_replay_without_the_original_provider() {
+    let fixtures = tempfile::tempdir().unwrap();
+    let mock = MockProvider::new().with_response("system", "diff", "fix: handle empty input");
+    let recorder = ReplayProvider::record(Box::new(mock), fixtures.path());
+    recorder.generate_text("mock", "system", "diff", 0.7).await.unwrap();
+
+    let replay = ReplayProvider::replay(fixtures.path());
+
+    assert_eq!(replay.generate_text("other", "system", "diff", 0.0).await.unwrap(), "fix: handle empty input");
+    assert!(replay.generate_text("other", "system", "unrecorded", 0.0).await.is_err());
+}
+
+#[tokio::test]
+async fn oversized_diff_is_summarised_in_batches_of_files() {
+    let provider = MockProvider::new()
+        .with_default_response("Summary of one file.")
+        .with_context_window(2_000);
+    let requests = provider.request_log();
+    let analyzer = GitAnalyzerImpl::new(Box::new(provider), None)
+        .with_prompt(Task::CommitMessage, "Write a commit message.".to_string());
+    let file_diff = |path: &str| format!("diff --git a/{0} b/{0}\n@@ -1 +1 @@\n{1}", path, "+changed line\n".repeat(150));
+    let small_diff = |path: &str| format!("diff --git a/{0} b/{0}\n@@ -1 +1 @@\n+changed line\n", path);
+    let diff = file_diff("src/a.rs") + &file_diff("src/b.rs") + &small_diff("src/c.rs") + &small_diff("src/d.rs");
+
+    analyzer.generate_commit_message(&diff).await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 3);
+    assert!(requests[0].user_input.starts_with("diff --git a/src/a.rs"));
+    assert!(requests[1].user_input.starts_with("diff --git a/src/b.rs"));
+    assert!(requests[1].user_input.contains("diff --git a/src/d.rs"));
+    assert_eq!(requests[2].system_prompt, "Write a commit message.");
+    assert!(requests[2].user_input.contains("## src/a.rs\nSummary of one file."));
+    assert!(requests[2].user_input.contains("## src/b.rs, src/c.rs, src/d.rs\nSummary of one file."));
+}
+
+/// Accepts the request, then reports `overloaded_error` before any text, as Anthropic's streams can
+#[derive(Debug)]
+struct OverloadedProvider;
+
+#[async_trait]
+impl Provider for OverloadedProvider {
+    fn name(&self) -> &str {
+        "Overloaded"
+    }
+
+    fn default_model(&self) -> &str {
+        "overloaded"
+    }
+
+    async fn generate_text(&self, _: &str, _: &str, _: &str, _: f32) -> Result<String, Box<dyn std::error::Error>> {
+        unreachable!("only streams are requested")
+    }
+
+    async fn generate_text_stream(&self, _: &str, _: &str, _: &str, _: f32) -> Result<TextStream, Box<dyn std::error::Error>> {
+        let error = ProviderError::Http { provider: "Overloaded".to_string(), status: 529, message: "Overloaded".to_string() };
+        Ok(Box::pin(futures::stream::once(async move { Err(error.into()) })))
+    }
+}
+
+#[tokio::test]
+async fn stream_failing_before_its_first_chunk_falls_back() {
+    let analyzer = FallbackAnalyzer::new(vec![
+        Box::new(GitAnalyzerImpl::new(Box::new(OverloadedProvider), None)),
+        Box::new(GitAnalyzerImpl::new(Box::new(MockProvider::new().with_default_response("Looks fine.")), None)),
+    ]);
+
+    let chunks: Vec<_> = analyzer.analyze_file_changes_stream("diff").await.unwrap().collect().await;
+
+    assert_eq!(chunks.into_iter().map(Result::unwrap).collect::<String>(), "Looks fine.");
+    assert_eq!(analyzer.answered_by(), "Mock");
+}
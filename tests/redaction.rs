@@ -0,0 +1,53 @@
+use merit_cli_demo::redact::{RedactionPolicy, Redactor, SecretKind};
+
+const ENV_DIFF: &str = "diff --git a/.env b/.env
+new file
+@@ -0,0 +1,3 @@
++OPENAI_API_KEY=sk-proj-abcdefghijklmnopqrstuvwxyz0123
++DB_PASSWORD=hunter22
++ADMIN=jane.doe@example.com
+diff --git a/src/config.rs b/src/config.rs
+@@ -1 +1 @@
+-    password: String,
++    api_key: Option<String>,
+";
+
+#[test]
+fn secrets_are_masked_and_reported_by_file() {
+    let redacted = Redactor::default().redact(ENV_DIFF);
+
+    assert!(!redacted.text.contains("sk-proj-"));
+    assert!(!redacted.text.contains("hunter22"));
+    assert!(!redacted.text.contains("jane.doe@example.com"));
+    assert!(redacted.text.contains("+    api_key: Option<String>,"));
+    let kinds: Vec<SecretKind> = redacted.findings.iter().map(|f| f.kind).collect();
+    assert_eq!(kinds, [SecretKind::ApiKey, SecretKind::Credential, SecretKind::Email]);
+    assert!(redacted.findings.iter().all(|f| f.file.as_deref() == Some(".env")));
+}
+
+#[test]
+fn block_policy_refuses_text_with_secrets() {
+    let redactor = Redactor::new(&[r"@example\.com$".to_string()]).unwrap();
+
+    let blocked = redactor.apply(RedactionPolicy::Block, ENV_DIFF).unwrap_err();
+
+    assert_eq!(blocked.findings.len(), 2);
+    assert!(redactor.apply(RedactionPolicy::Block, "+let x = 1;\n").is_ok());
+}
+
+#[test]
+fn ordinary_code_is_left_alone() {
+    let code = "+let max_tokens = 128_000;
++    context_window_tokens: 200000,
++use crate::providers::OpenAICompatibleProvider2Config;
++const DEFAULT_SESSION_TOKEN_REFRESH_INTERVAL: u64 = 3600;
+";
+
+    let redacted = Redactor::default().redact(code);
+
+    assert_eq!(redacted.text, code);
+    assert!(redacted.findings.is_empty());
+    assert!(Redactor::default().apply(RedactionPolicy::Block, code).is_ok());
+    let token = Redactor::default().redact("+const SEED: &str = \"aZ3kQ9xL2mP7wR4tY8vB1nC6\";\n");
+    assert_eq!(token.findings.len(), 1);
+}
@@ -0,0 +1,51 @@
+use merit_cli_demo::changeset::{ChangeStatus, FileDiff, Hunk, Line};
+use merit_cli_demo::review::{annotate, count_at_least, parse_findings, render_sarif, Category, Severity};
+
+fn file_with_hunks() -> FileDiff {
+    let line = |origin, content: &str| Line { origin, content: format!("{}\n", content) };
+    let hunk = |header: &str, new_start, new_lines, lines| Hunk {
+        header: header.to_string(),
+        old_start: new_start,
+        old_lines: new_lines,
+        new_start,
+        new_lines,
+        lines,
+    };
+    FileDiff {
+        path: "src/parse.rs".to_string(),
+        old_path: None,
+        status: ChangeStatus::Modified,
+        binary: false,
+        mode_change: None,
+        hunks: vec![
+            hunk("@@ -10,2 +10,2 @@", 10, 2, vec![line(' ', "let a = 1;"), line('-', "let b = 2;"), line('+', "let b = 3;")]),
+            hunk("@@ -40,1 +40,2 @@", 40, 2, vec![line(' ', "}"), line('+', "fn f() {}")]),
+        ],
+        omitted_lines: 0,
+        omitted: None,
+    }
+}
+
+#[test]
+fn findings_are_placed_on_hunk_lines_and_exported() {
+    let file = file_with_hunks();
+    assert!(annotate(&file).contains("@@ -10,2 +10,2 @@\n   10  let a = 1;\n      -let b = 2;\n   11 +let b = 3;\n"));
+
+    let answer = r#"Here you go: {"findings": [
+        {"start_line": 11, "severity": "high", "category": "bug", "message": "Wrong value", "suggestion": "Use 2"},
+        {"start_line": 44, "end_line": 50, "severity": "Warning", "category": "performance", "message": "Slow"}
+    ]}"#;
+    let findings = parse_findings(answer, &file).unwrap();
+
+    assert_eq!(findings.len(), 2);
+    assert_eq!((findings[0].start_line, findings[0].end_line, findings[0].severity), (11, 11, Severity::High));
+    assert_eq!(findings[1].hunk, "@@ -40,1 +40,2 @@");
+    assert_eq!((findings[1].start_line, findings[1].end_line), (41, 41));
+    assert_eq!((findings[1].severity, findings[1].category), (Severity::Medium, Category::Perf));
+    assert_eq!(count_at_least(&findings, Severity::High), 1);
+
+    let sarif: serde_json::Value = serde_json::from_str(&render_sarif(&findings).unwrap()).unwrap();
+    let result = &sarif["runs"][0]["results"][0];
+    assert_eq!(result["level"], "error");
+    assert_eq!(result["locations"][0]["physicalLocation"]["region"]["startLine"], 11);
+}
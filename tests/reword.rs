@@ -0,0 +1,41 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::{commit_file, init_repo};
+use merit_cli_demo::commit::{reword_commits, SigningMode};
+use merit_cli_demo::commit_message::CommitMessage;
+use merit_cli_demo::git::{commits_after, pushed_commits};
+
+#[test]
+fn poor_messages_score_low_and_are_reworded_in_place() {
+    let types = vec!["feat".to_string(), "fix".to_string()];
+    assert_eq!(CommitMessage::parse("feat(api): add export").score(&types).score, 100);
+    assert!(CommitMessage::parse("fix").score(&types).score < 30);
+    assert_eq!(CommitMessage::parse("feat: wip").score(&types).problems.len(), 1);
+
+    let dir = tempfile::tempdir().unwrap();
+    let repo = init_repo(dir.path());
+    let root = commit_file(&repo, "a.txt", "a\n", "chore: initial commit");
+    let wip = commit_file(&repo, "b.txt", "b\n", "wip");
+    let good = commit_file(&repo, "c.txt", "c\n", "feat: add c");
+    let old_tree = repo.head().unwrap().peel_to_tree().unwrap().id();
+
+    let ids: Vec<git2::Oid> = commits_after(&repo, Some(&root.to_string())).unwrap().iter().map(|c| c.id()).collect();
+    assert_eq!(ids, [wip, good]);
+    assert!(pushed_commits(&repo, &ids).unwrap().is_empty());
+    repo.reference("refs/remotes/origin/master", wip, true, "fetch").unwrap();
+    assert_eq!(pushed_commits(&repo, &ids).unwrap(), [wip]);
+
+    let messages = HashMap::from([(wip, "feat: add b".to_string())]);
+    let tip = reword_commits(&repo, Some(root), &messages, SigningMode::Off).unwrap();
+
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    assert_eq!(head.id(), tip);
+    assert_ne!(tip, good);
+    assert_eq!(head.message(), Some("feat: add c"));
+    assert_eq!(head.tree_id(), old_tree);
+    let parent = head.parent(0).unwrap();
+    assert_eq!(parent.message(), Some("feat: add b\n"));
+    assert_eq!(parent.parent_id(0).unwrap(), root);
+}
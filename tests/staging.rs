@@ -0,0 +1,67 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::{commit_file, init_repo};
+use git2::Repository;
+use merit_cli_demo::git::{get_file_changes, get_unstaged_changes, stage_files, stage_hunks};
+
+#[test]
+fn later_hunk_is_staged_when_an_earlier_one_is_skipped() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = init_repo(dir.path());
+    let original: Vec<String> = (1..=40).map(|n| format!("line {}", n)).collect();
+    commit_file(&repo, "file.txt", &(original.join("\n") + "\n"), "chore: initial commit");
+
+    // Five lines added near the top, then one line changed further down
+    let mut modified = original.clone();
+    for n in 0..5 {
+        modified.insert(2, format!("inserted {}", n));
+    }
+    modified[40] = "line 36 changed".to_string();
+    fs::write(dir.path().join("file.txt"), modified.join("\n") + "\n").unwrap();
+
+    let mut file = get_unstaged_changes(&repo).unwrap().files.remove(0);
+    assert_eq!(file.hunks.len(), 2);
+    file.hunks.remove(0);
+    stage_hunks(&repo, &[file]).unwrap();
+
+    let mut index = repo.index().unwrap();
+    index.read(true).unwrap();
+    let entry = index.get_path(Path::new("file.txt"), 0).unwrap();
+    let staged = String::from_utf8(repo.find_blob(entry.id).unwrap().content().to_vec()).unwrap();
+    let mut expected = original;
+    expected[35] = "line 36 changed".to_string();
+    assert_eq!(staged, expected.join("\n") + "\n");
+}
+
+#[test]
+fn picked_partly_staged_file_keeps_its_staged_hunks() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = init_repo(dir.path());
+    let original: Vec<String> = (1..=40).map(|n| format!("line {}", n)).collect();
+    commit_file(&repo, "file.txt", &(original.join("\n") + "\n"), "chore: initial commit");
+
+    let mut modified = original.clone();
+    modified[0] = "line 1 changed".to_string();
+    modified[39] = "line 40 changed".to_string();
+    fs::write(dir.path().join("file.txt"), modified.join("\n") + "\n").unwrap();
+    let mut file = get_unstaged_changes(&repo).unwrap().files.remove(0);
+    file.hunks.truncate(1);
+    stage_hunks(&repo, &[file]).unwrap();
+    let staged_id = |repo: &Repository| {
+        let mut index = repo.index().unwrap();
+        index.read(true).unwrap();
+        index.get_path(Path::new("file.txt"), 0).unwrap().id
+    };
+    let partial = staged_id(&repo);
+
+    let changes = get_file_changes(&repo).unwrap();
+    assert!(changes[0].staged && changes[0].unstaged);
+    stage_files(&repo, &changes, &["file.txt"], &["file.txt"]).unwrap();
+    assert_eq!(staged_id(&repo), partial);
+
+    stage_files(&repo, &changes, &["file.txt"], &[]).unwrap();
+    assert_ne!(staged_id(&repo), partial);
+}